@@ -0,0 +1,48 @@
+use osu_format::HitObject;
+
+use crate::replay::ReplayEvent;
+
+// how long a circle is held before letting go
+const TAP_DURATION: f32 = 40.0;
+
+fn column_events(column: usize, map: &[HitObject]) -> Vec<ReplayEvent> {
+    let mut events = Vec::new();
+    for (i, hit_object) in map.iter().enumerate() {
+        let (press, release) = match hit_object {
+            HitObject::Circle { base } => (base.time as f32, base.time as f32 + TAP_DURATION),
+            HitObject::LongNote { base, end_time } => (base.time as f32, *end_time as f32),
+            _ => continue,
+        };
+        // let go before the next note in the column so it gets a fresh press
+        let next_press = map.get(i + 1).map_or(f32::MAX, |next| match next {
+            HitObject::Circle { base } | HitObject::LongNote { base, .. } => base.time as f32,
+            _ => f32::MAX,
+        });
+        // a long note is held right up to its tail, only the gap after it is shared
+        let release = match hit_object {
+            HitObject::LongNote { .. } => release.min((release + next_press) / 2.0),
+            _ => release.min((press + next_press) / 2.0),
+        };
+        events.push(ReplayEvent {
+            position: press,
+            column,
+            pressed: true,
+        });
+        events.push(ReplayEvent {
+            position: release,
+            column,
+            pressed: false,
+        });
+    }
+    events
+}
+
+// perfectly timed presses and releases for every note of every lane
+pub fn generate<'a>(lane_maps: impl Iterator<Item = &'a [HitObject]>) -> Vec<ReplayEvent> {
+    let mut events: Vec<ReplayEvent> = lane_maps
+        .enumerate()
+        .flat_map(|(column, map)| column_events(column, map))
+        .collect();
+    events.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap());
+    events
+}
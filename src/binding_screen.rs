@@ -0,0 +1,134 @@
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Col, Img},
+        Color, Font, FontStyle,
+    },
+    input::{ButtonState, Key},
+    lifecycle::{Asset, Event, Window},
+    Result,
+};
+
+use crate::bindings::{key_name, Bindings, BINDINGS_PATH, MAX_KEYS};
+use crate::scene::{Scene, Transition};
+
+const COLUMN_WIDTH: f32 = 90.0;
+const COLUMN_HEIGHT: f32 = 300.0;
+
+pub struct BindingScreen {
+    bindings: Bindings,
+    keys: u32,
+    // column waiting for a key, none while choosing the key count
+    binding_column: Option<usize>,
+    done: bool,
+    font: Asset<Font>,
+}
+
+impl BindingScreen {
+    pub fn new(keys: u32) -> Result<BindingScreen> {
+        Ok(BindingScreen {
+            bindings: Bindings::load(BINDINGS_PATH),
+            keys: keys.clamp(1, MAX_KEYS),
+            binding_column: None,
+            done: false,
+            font: Asset::new(Font::load("static/fonts/DejaVuSans.ttf")),
+        })
+    }
+
+    fn press(&mut self, key: Key) -> Result<()> {
+        match self.binding_column {
+            Some(column) => {
+                if key == Key::Escape {
+                    self.bindings = Bindings::load(BINDINGS_PATH);
+                    self.binding_column = None;
+                } else if Bindings::is_bindable(key) {
+                    self.bindings.bind(self.keys, column, key);
+                    if column + 1 < self.keys as usize {
+                        self.binding_column = Some(column + 1);
+                    } else {
+                        self.binding_column = None;
+                        self.bindings.save(BINDINGS_PATH)?;
+                    }
+                }
+            }
+            None => match key {
+                Key::Escape => self.done = true,
+                Key::Left => self.keys = (self.keys - 1).max(1),
+                Key::Right => self.keys = (self.keys + 1).min(MAX_KEYS),
+                Key::Return => self.binding_column = Some(0),
+                _ => (),
+            },
+        }
+        Ok(())
+    }
+}
+
+impl Scene for BindingScreen {
+    fn update(&mut self, _window: &mut Window) -> Result<Transition> {
+        if self.done {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn event(&mut self, event: &Event, _window: &mut Window) -> Result<()> {
+        if let Event::Key(key, ButtonState::Pressed) = event {
+            self.press(*key)?;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+        window.clear(Color::BLACK)?;
+
+        let keys = self.keys;
+        let binding_column = self.binding_column;
+        let layout = self.bindings.layout(keys).unwrap_or_default();
+        let stage_x = (w - COLUMN_WIDTH * keys as f32) / 2.0;
+        let stage_y = (h - COLUMN_HEIGHT) / 2.0;
+        for column in 0..keys as usize {
+            let color = if Some(column) == binding_column {
+                Color::from_rgba(80, 160, 255, 0.8)
+            } else {
+                Color::from_rgba(255, 255, 255, 0.15)
+            };
+            window.draw_ex(
+                &Rectangle::new(
+                    (stage_x + column as f32 * COLUMN_WIDTH + 2.0, stage_y),
+                    (COLUMN_WIDTH - 4.0, COLUMN_HEIGHT),
+                ),
+                Col(color),
+                Transform::IDENTITY,
+                0,
+            );
+        }
+
+        self.font.execute(|font| {
+            let title = match binding_column {
+                Some(column) => format!("{}K: press a key for column {}", keys, column + 1),
+                None => format!("< {}K >  Enter to rebind, Escape to go back", keys),
+            };
+            let title = font.render(&title, &FontStyle::new(40.0, Color::WHITE))?;
+            window.draw_ex(
+                &title.area().with_center((w / 2.0, stage_y - 60.0)),
+                Img(&title),
+                Transform::IDENTITY,
+                1,
+            );
+            for (column, key) in layout.iter().enumerate() {
+                let name = font.render(&key_name(*key), &FontStyle::new(24.0, Color::WHITE))?;
+                window.draw_ex(
+                    &name.area().with_center((
+                        stage_x + (column as f32 + 0.5) * COLUMN_WIDTH,
+                        stage_y + COLUMN_HEIGHT - 30.0,
+                    )),
+                    Img(&name),
+                    Transform::IDENTITY,
+                    1,
+                );
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,209 @@
+use quicksilver::{input::Key, Result};
+use std::fmt::Write;
+
+pub const BINDINGS_PATH: &str = "keybindings.cfg";
+pub const MAX_KEYS: u32 = 10;
+
+const BINDABLE_KEYS: [Key; 51] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::Key0,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Space,
+    Key::Semicolon,
+    Key::Comma,
+    Key::Period,
+    Key::Slash,
+    Key::LBracket,
+    Key::RBracket,
+    Key::Apostrophe,
+    Key::Minus,
+    Key::LShift,
+    Key::RShift,
+    Key::LControl,
+    Key::RControl,
+    Key::LAlt,
+    Key::RAlt,
+];
+
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    BINDABLE_KEYS
+        .iter()
+        .find(|key| key_name(**key) == name)
+        .cloned()
+}
+
+// a key bound to two columns would press both of them
+fn has_duplicates(layout: &[Key]) -> bool {
+    layout
+        .iter()
+        .enumerate()
+        .any(|(i, key)| layout[..i].contains(key))
+}
+
+// osu!mania goes up to 18K, only this many have layouts
+pub fn is_supported(keys: u32) -> bool {
+    (1..=MAX_KEYS).contains(&keys)
+}
+
+// empty for key counts that aren't supported
+pub fn default_layout(keys: u32) -> Vec<Key> {
+    match keys {
+        1 => vec![Key::Space],
+        2 => vec![Key::F, Key::J],
+        3 => vec![Key::F, Key::Space, Key::J],
+        4 => vec![Key::D, Key::F, Key::J, Key::K],
+        5 => vec![Key::D, Key::F, Key::Space, Key::J, Key::K],
+        6 => vec![Key::S, Key::D, Key::F, Key::J, Key::K, Key::L],
+        7 => vec![Key::S, Key::D, Key::F, Key::Space, Key::J, Key::K, Key::L],
+        8 => vec![
+            Key::A,
+            Key::S,
+            Key::D,
+            Key::F,
+            Key::J,
+            Key::K,
+            Key::L,
+            Key::Semicolon,
+        ],
+        9 => vec![
+            Key::A,
+            Key::S,
+            Key::D,
+            Key::F,
+            Key::Space,
+            Key::J,
+            Key::K,
+            Key::L,
+            Key::Semicolon,
+        ],
+        10 => vec![
+            Key::A,
+            Key::S,
+            Key::D,
+            Key::F,
+            Key::V,
+            Key::N,
+            Key::J,
+            Key::K,
+            Key::L,
+            Key::Semicolon,
+        ],
+        _ => Vec::new(),
+    }
+}
+
+pub struct Bindings {
+    // layouts[keys - 1] holds the keys for a `keys`K map
+    layouts: Vec<Vec<Key>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings {
+            layouts: (1..=MAX_KEYS).map(default_layout).collect(),
+        }
+    }
+}
+
+impl Bindings {
+    // lines look like `4K: D F J K`, missing or broken lines and layouts that use a key
+    // twice keep the default layout
+    pub fn parse(text: &str) -> Bindings {
+        let mut bindings = Bindings::default();
+        for line in text.lines() {
+            let mut parts = line.splitn(2, ':');
+            let keys = parts
+                .next()
+                .and_then(|count| count.trim().trim_end_matches('K').parse::<u32>().ok());
+            let layout: Option<Vec<Key>> = parts
+                .next()
+                .and_then(|names| names.split_whitespace().map(parse_key).collect());
+            if let (Some(keys), Some(layout)) = (keys, layout) {
+                if is_supported(keys) && layout.len() == keys as usize && !has_duplicates(&layout) {
+                    bindings.layouts[keys as usize - 1] = layout;
+                }
+            }
+        }
+        bindings
+    }
+
+    pub fn load(path: &str) -> Bindings {
+        std::fs::read_to_string(path)
+            .map(|text| Bindings::parse(&text))
+            .unwrap_or_else(|_| Bindings::default())
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let mut text = String::new();
+        for layout in &self.layouts {
+            let names: Vec<String> = layout.iter().map(|key| key_name(*key)).collect();
+            writeln!(text, "{}K: {}", layout.len(), names.join(" ")).unwrap();
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn layout(&self, keys: u32) -> Option<&[Key]> {
+        if !is_supported(keys) {
+            return None;
+        }
+        Some(&self.layouts[keys as usize - 1])
+    }
+
+    pub fn is_bindable(key: Key) -> bool {
+        BINDABLE_KEYS.contains(&key)
+    }
+
+    // a column that already uses `key` gets this column's old key, so no key is bound twice
+    pub fn bind(&mut self, keys: u32, column: usize, key: Key) {
+        if !is_supported(keys) {
+            return;
+        }
+        let layout = &mut self.layouts[keys as usize - 1];
+        if column >= layout.len() {
+            return;
+        }
+        if let Some(other) = layout.iter().position(|bound| *bound == key) {
+            layout.swap(column, other);
+        } else {
+            layout[column] = key;
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use osu_format::Event;
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Blended, Col},
+        Color, Image,
+    },
+    lifecycle::{Asset, Window},
+    sound::Sound,
+    Result,
+};
+
+// the background dim lifts and comes back over this long at the edges of a break
+const DIM_FADE: f32 = 500.0;
+// section pass/fail shows up this long before a break ends
+const SECTION_LEAD: f32 = 1500.0;
+const SECTION_DURATION: f32 = 1000.0;
+const PROGRESS_WIDTH: f32 = 400.0;
+const PROGRESS_HEIGHT: f32 = 8.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Break {
+    pub start: f32,
+    pub end: f32,
+}
+
+// The breaks of a map, from its [Events]
+pub struct Breaks {
+    breaks: Vec<Break>,
+    // the break whose section pass/fail comes next
+    next_section: usize,
+    // when the last section pass/fail started and whether it was a pass
+    section: Option<(f32, bool)>,
+    asset_pass: Asset<Image>,
+    asset_fail: Asset<Image>,
+    asset_pass_sound: Asset<Sound>,
+    asset_fail_sound: Asset<Sound>,
+}
+
+impl Breaks {
+    pub fn new(events: &[Event]) -> Breaks {
+        let mut breaks: Vec<Break> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Break {
+                    start_time,
+                    end_time,
+                } => Some(Break {
+                    start: *start_time as f32,
+                    end: *end_time as f32,
+                }),
+                _ => None,
+            })
+            .collect();
+        breaks.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+        Breaks {
+            breaks,
+            next_section: 0,
+            section: None,
+            asset_pass: Asset::new(Image::load("static/skin/section-pass.png")),
+            asset_fail: Asset::new(Image::load("static/skin/section-fail.png")),
+            asset_pass_sound: Asset::new(Sound::load("static/skin/sectionpass.mp3")),
+            asset_fail_sound: Asset::new(Sound::load("static/skin/sectionfail.mp3")),
+        }
+    }
+
+    pub fn current(&self, position: f32) -> Option<Break> {
+        self.breaks
+            .iter()
+            .find(|b| b.start <= position && position < b.end)
+            .cloned()
+    }
+
+    // 0 while playing, 1 when the dim is fully lifted
+    pub fn dim_lift(&self, position: f32) -> f32 {
+        self.current(position).map_or(0.0, |b| {
+            ((position - b.start).min(b.end - position) / DIM_FADE).clamp(0.0, 1.0)
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.next_section = 0;
+        self.section = None;
+    }
+
+    pub fn update(&mut self, position: f32, passing: bool) -> Result<()> {
+        while let Some(b) = self.breaks.get(self.next_section).cloned() {
+            let time = b.end - SECTION_LEAD;
+            if time > position {
+                break;
+            }
+            self.next_section += 1;
+            // too short a break, or skipped past
+            if time < b.start || position >= b.end {
+                continue;
+            }
+            self.section = Some((time, passing));
+            let sound = if passing {
+                &mut self.asset_pass_sound
+            } else {
+                &mut self.asset_fail_sound
+            };
+            sound.execute(|sound| sound.play())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw(&mut self, window: &mut Window, position: f32) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+        if let Some(b) = self.current(position) {
+            let remaining = (b.end - position) / (b.end - b.start);
+            let alpha = self.dim_lift(position);
+            window.draw_ex(
+                &Rectangle::new((0, 0), (PROGRESS_WIDTH * remaining, PROGRESS_HEIGHT))
+                    .with_center((w / 2.0, h * 0.2)),
+                Col(Color::WHITE.with_alpha(0.6 * alpha)),
+                Transform::IDENTITY,
+                6,
+            );
+        }
+
+        if let Some((time, passed)) = self.section {
+            let progress = (position - time) / SECTION_DURATION;
+            if !(0.0..1.0).contains(&progress) {
+                return Ok(());
+            }
+            let asset = if passed {
+                &mut self.asset_pass
+            } else {
+                &mut self.asset_fail
+            };
+            asset.execute(|image| {
+                window.draw_ex(
+                    &image.area().with_center((w / 2.0, h / 2.0)),
+                    Blended(&image, Color::WHITE.with_alpha(1.0 - progress)),
+                    Transform::IDENTITY,
+                    6,
+                );
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
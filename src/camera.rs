@@ -0,0 +1,749 @@
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::Instant;
+
+use quicksilver::{
+    geom::{Line, Rectangle, Shape, Transform, Vector},
+    graphics::{Background::Col, Background::Img, Color, Font, FontStyle, Image},
+    input::{ButtonState, Key},
+    lifecycle::{Asset, Event, Window},
+    Result,
+};
+
+use crate::autoplay;
+use crate::bindings::{self, Bindings, BINDINGS_PATH};
+use crate::breaks::Breaks;
+use crate::clock::SongClock;
+use crate::columns;
+use crate::countdown::Countdown;
+use crate::fail::FailScreen;
+use crate::health::Health;
+use crate::hit_score::HitScore;
+use crate::hitsound::{self, Hitsounds, Sample};
+use crate::input::InputQueue;
+use crate::judgement::{HitWindows, Judgement};
+use crate::lane::{Lane, LaneSkin};
+use crate::library;
+use crate::mods::{Mod, ModIcons, Mods};
+use crate::nightcore::NightcoreBeat;
+use crate::number::Number;
+use crate::osr::OsrReplay;
+use crate::pause::{PauseChoice, PauseMenu};
+use crate::replay::{Replay, ReplayPlayer};
+use crate::results::Results;
+use crate::scene::{Scene, Transition};
+use crate::score::{Score, ScoreMode};
+use crate::scroll::ScrollSpeed;
+use crate::skin::{ManiaLayout, Skin, SKIN_HEIGHT, SKIN_WIDTH};
+use crate::timeline::Timeline;
+
+use osu_format::HitObject;
+
+// how long to wait after the last note before showing the results
+const END_DELAY: f32 = 1500.0;
+const LIFE_BAR_INTERVAL: f32 = 2000.0;
+// counts down from 3 before the song continues after a pause
+const RESUME_STEP: f32 = 500.0;
+const RESUME_STEPS: u32 = 3;
+// how long the quick restart key has to be held
+const RESTART_HOLD: f32 = 500.0;
+const RESTART_KEY: Key = Key::Grave;
+// the first note never comes sooner than this after the clock starts
+const MIN_LEAD_IN: f32 = 2000.0;
+// skipping lands this long before the next note
+const SKIP_MARGIN: f32 = 2000.0;
+// shorter intros and breaks aren't worth a skip prompt
+const MIN_SKIP: f32 = 1000.0;
+const BACKGROUND_DIM: f32 = 0.8;
+// a break ends in section pass with at least this much HP and accuracy
+const SECTION_PASS_HP: f32 = 0.5;
+const SECTION_PASS_ACCURACY: f32 = 0.8;
+// Space is a lane key on odd layouts
+const SKIP_KEY: Key = Key::Return;
+
+#[derive(PartialEq, Clone, Copy)]
+enum GameState {
+    Playing,
+    Paused,
+    // counting down since the instant to continue
+    Resuming(Instant),
+    Failed,
+}
+
+pub struct Camera {
+    timeline: Timeline,
+    scroll_speed: ScrollSpeed,
+    font: Asset<Font>,
+    score: Score,
+    asset_bg: Option<Asset<Image>>,
+    hit_score: HitScore,
+    health: Health,
+    fail_screen: FailScreen,
+    pause_menu: PauseMenu,
+    // set by the pause menu, handed over on the next update
+    pending: Option<Transition>,
+    // when the quick restart key went down
+    restart_held: Option<Instant>,
+    clock: SongClock,
+    input: InputQueue,
+    state: GameState,
+    number: Number,
+    combo_number: Number,
+    lanes: Vec<Lane>,
+    hotkeys: Vec<Key>,
+    replay: Replay,
+    playback: Option<ReplayPlayer>,
+    beatmap_hash: String,
+    mods: Mods,
+    mod_icons: ModIcons,
+    // (position, hp) samples for the .osr life bar graph
+    life_bar: Vec<(f32, f32)>,
+    // samples of every hit object, indexed like the lane maps
+    lane_samples: Vec<Vec<Vec<Sample>>>,
+    hitsounds: Hitsounds,
+    layout: ManiaLayout,
+    end_position: f32,
+    // where skipping the intro goes to
+    intro_skip: f32,
+    asset_skip: Asset<Image>,
+    breaks: Breaks,
+    countdown: Option<Countdown>,
+    nightcore: Option<NightcoreBeat>,
+}
+
+fn unsupported_key_count(keys: u32) -> quicksilver::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("{}K maps are not supported", keys),
+    )
+    .into()
+}
+
+fn x_to_lane(x: u32, lane_count: u32) -> usize {
+    (x as f32 / (512.0 / lane_count as f32))
+        .floor()
+        .clamp(0.0, (lane_count - 1) as f32) as usize
+}
+
+fn new_lanes(
+    count: usize,
+    lane_maps: Vec<Vec<HitObject>>,
+    hotkeys: &[Key],
+    windows: HitWindows,
+) -> Result<Vec<Lane>> {
+    (0..count)
+        .map(|i| {
+            // columns mirror around the middle, which gets the special skin on odd key counts
+            let distance_from_edge = i.min(count - 1 - i);
+            let lane_skin = if count % 2 == 1 && i == count / 2 {
+                LaneSkin::LaneS
+            } else if distance_from_edge % 2 == 0 {
+                LaneSkin::Lane1
+            } else {
+                LaneSkin::Lane2
+            };
+            Lane::new(lane_skin, &lane_maps[i], hotkeys[i], windows)
+        })
+        .collect()
+}
+
+impl Camera {
+    pub fn with_replay(replay: Replay) -> Result<Camera> {
+        let mut camera = Camera::load(&replay.map_path, replay.mods, replay.seed)?;
+        camera.playback = Some(ReplayPlayer::new(replay));
+        Ok(camera)
+    }
+
+    pub fn new(map_path: &str, mods: Mods) -> Result<Camera> {
+        Camera::load(map_path, mods, columns::random_seed())
+    }
+
+    // `seed` picks the Random mod's column order
+    fn load(map_path: &str, mods: Mods, seed: u32) -> Result<Camera> {
+        let f = std::fs::File::open(map_path)?;
+        let f = std::io::BufReader::new(f);
+        let beatmap = osu_format::Parser::new(f.lines()).parse().map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to parse {}: {:?}", map_path, err),
+            )
+        })?;
+        let note_count = beatmap.difficulty.circle_size as u32;
+        if !bindings::is_supported(note_count) {
+            return Err(unsupported_key_count(note_count));
+        }
+        let bg = beatmap.events.iter().find_map(|event| match event {
+            osu_format::Event::BackgroundMedia { filepath } => Some(
+                Path::new(map_path)
+                    .parent()
+                    .map(|par| par.join(filepath).to_string_lossy().into_owned())
+                    .unwrap_or(filepath.to_string()),
+            ),
+            _ => None,
+        });
+        let music = Path::new(map_path)
+            .parent()
+            .map(|par| {
+                par.join(&beatmap.general.audio_filename)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .unwrap_or(beatmap.general.audio_filename.to_string());
+        let lane_maps = beatmap.hit_objects.iter().fold(
+            vec![Vec::new(); note_count as usize],
+            |mut acc, hit_object| {
+                match hit_object {
+                    HitObject::Circle { base } => {
+                        acc[x_to_lane(base.x, note_count)].push(hit_object.clone())
+                    }
+                    HitObject::LongNote { base, .. } => {
+                        acc[x_to_lane(base.x, note_count)].push(hit_object.clone())
+                    }
+                    _ => (),
+                };
+                acc
+            },
+        );
+        let lane_maps = match mods.key_count() {
+            Some(keys) => columns::convert(lane_maps, keys),
+            None => lane_maps,
+        };
+        let lane_maps = if mods.contains(Mod::Random) {
+            columns::shuffle(lane_maps, seed)
+        } else if mods.contains(Mod::Mirror) {
+            columns::mirror(lane_maps)
+        } else {
+            lane_maps
+        };
+        let note_count = lane_maps.len() as u32;
+
+        let hotkeys = Bindings::load(BINDINGS_PATH)
+            .layout(note_count)
+            .ok_or_else(|| unsupported_key_count(note_count))?
+            .to_vec();
+
+        let beatmap_directory = Path::new(map_path).parent().unwrap_or(Path::new(""));
+        let default_set = hitsound::default_sample_set(&beatmap.general.sample_set);
+        let lane_samples: Vec<Vec<Vec<Sample>>> = lane_maps
+            .iter()
+            .map(|lane_map| {
+                lane_map
+                    .iter()
+                    .map(|hit_object| match hit_object {
+                        HitObject::Circle { base } | HitObject::LongNote { base, .. } => {
+                            hitsound::resolve(
+                                base,
+                                &beatmap.timing_points,
+                                default_set,
+                                beatmap_directory,
+                            )
+                        }
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let hitsounds = Hitsounds::new(lane_samples.iter().flatten().flatten());
+
+        let asset_bg = bg.map(|bg| Asset::new(Image::load(bg)));
+
+        let total_judgements = lane_maps
+            .iter()
+            .flatten()
+            .map(|hit_object| match hit_object {
+                HitObject::LongNote { .. } => 2,
+                _ => 1,
+            })
+            .sum();
+        let score_mode = if std::env::args().any(|arg| arg == "--scorev2") {
+            ScoreMode::V2
+        } else {
+            ScoreMode::V1
+        };
+
+        let scroll_speed = std::env::args()
+            .find(|arg| arg.starts_with("--scroll="))
+            .and_then(|arg| ScrollSpeed::parse(&arg["--scroll=".len()..]))
+            .unwrap_or_default();
+        let honour_sv = !std::env::args().any(|arg| arg == "--nosv");
+
+        let windows = mods.hit_windows(beatmap.difficulty.overall_difficulty);
+        let end_position = lane_maps
+            .iter()
+            .flatten()
+            .map(|hit_object| match hit_object {
+                HitObject::LongNote { end_time, .. } => *end_time,
+                HitObject::Circle { base } => base.time,
+                _ => unreachable!(),
+            })
+            .max()
+            .unwrap_or(0) as f32
+            + windows.miss();
+        let first_note = lane_maps
+            .iter()
+            .filter_map(|lane_map| lane_map.first())
+            .map(|hit_object| match hit_object {
+                HitObject::Circle { base } | HitObject::LongNote { base, .. } => base.time,
+                _ => unreachable!(),
+            })
+            .min()
+            .unwrap_or(0) as f32;
+        let countdown = Countdown::new(
+            beatmap.general.countdown,
+            &beatmap.timing_points,
+            first_note,
+        );
+        // there has to be time for the whole countdown too
+        let lead_in = (beatmap.general.audio_lead_in as f32)
+            .max(MIN_LEAD_IN - first_note)
+            .max(
+                countdown
+                    .as_ref()
+                    .map_or(0.0, |countdown| -countdown.start()),
+            );
+        let mut camera = Camera {
+            timeline: Timeline::new(&beatmap.timing_points, end_position, honour_sv),
+            scroll_speed,
+            font: Asset::new(Font::load("static/fonts/DejaVuSans.ttf")),
+            score: Score::new(total_judgements, score_mode)
+                .with_multiplier(mods.score_multiplier()),
+            hit_score: HitScore::new().unwrap(),
+            health: Health::new(mods.hp_drain_rate(beatmap.difficulty.hp_drain_rate)).unwrap(),
+            fail_screen: FailScreen::new().unwrap(),
+            pause_menu: PauseMenu::new()?,
+            pending: None,
+            restart_held: None,
+            asset_bg,
+            clock: SongClock::new(&music, mods.rate(), !mods.contains(Mod::Nightcore))?
+                .with_lead_in(lead_in),
+            input: InputQueue::new(),
+            number: Number::new("score")
+                .unwrap()
+                .with_symbol('.', "static/skin/score-dot.png")
+                .with_symbol('%', "static/skin/score-percent.png"),
+            combo_number: Number::new("combo")
+                .unwrap()
+                .with_symbol('x', "static/skin/combo-x.png"),
+            state: GameState::Playing,
+            lanes: new_lanes(note_count as usize, lane_maps, &hotkeys, windows).unwrap(),
+            hotkeys,
+            replay: Replay::new(map_path, mods, seed),
+            playback: None,
+            mods,
+            mod_icons: ModIcons::new(),
+            beatmap_hash: library::beatmap_hash(Path::new(map_path))?,
+            life_bar: Vec::new(),
+            lane_samples,
+            hitsounds,
+            layout: Skin::load("static/skin/Skin.ini")?.mania(note_count),
+            end_position,
+            intro_skip: first_note - SKIP_MARGIN,
+            breaks: Breaks::new(&beatmap.events),
+            asset_skip: Asset::new(Image::load("static/skin/play-skip.png")),
+            countdown,
+            nightcore: if mods.contains(Mod::Nightcore) {
+                Some(NightcoreBeat::new(&beatmap.timing_points, end_position))
+            } else {
+                None
+            },
+        };
+        if mods.is_autoplay() {
+            let events = autoplay::generate(camera.lanes.iter().map(|lane| lane.map()));
+            camera.playback = Some(ReplayPlayer::new(Replay {
+                map_path: map_path.to_string(),
+                mods,
+                seed,
+                events,
+            }));
+        }
+        Ok(camera)
+    }
+
+    fn pause(&mut self) {
+        self.clock.pause();
+        self.pause_menu.open(false);
+        self.state = GameState::Paused;
+    }
+
+    // back to the start of the map, keeping everything that's already loaded
+    fn restart(&mut self) -> Result<()> {
+        for lane in &mut self.lanes {
+            lane.reset();
+        }
+        self.score.reset();
+        self.hit_score.reset();
+        self.health.reset();
+        self.fail_screen.reset();
+        self.clock.restart()?;
+        self.input = InputQueue::new();
+        self.replay = Replay::new(&self.replay.map_path, self.mods, self.replay.seed);
+        if let Some(playback) = &mut self.playback {
+            playback.restart();
+        }
+        if let Some(nightcore) = &mut self.nightcore {
+            nightcore.reset();
+        }
+        if let Some(countdown) = &mut self.countdown {
+            countdown.reset();
+        }
+        self.breaks.reset();
+        self.life_bar.clear();
+        self.restart_held = None;
+        self.state = GameState::Playing;
+        Ok(())
+    }
+
+    // where skipping goes right now, the end of the intro or of a long break
+    fn skip_target(&self) -> Option<f32> {
+        if self.state != GameState::Playing {
+            return None;
+        }
+        let position = self.clock.position();
+        let target = self
+            .breaks
+            .current(position)
+            .map_or(self.intro_skip, |b| b.end - SKIP_MARGIN);
+        if position + MIN_SKIP < target {
+            Some(target)
+        } else {
+            None
+        }
+    }
+
+    fn restart_progress(&self) -> f32 {
+        self.restart_held.map_or(0.0, |held| {
+            (held.elapsed().as_secs_f32() * 1000.0 / RESTART_HOLD).min(1.0)
+        })
+    }
+
+    fn choose(&mut self, choice: PauseChoice) -> Result<()> {
+        match choice {
+            PauseChoice::Continue => self.state = GameState::Resuming(Instant::now()),
+            PauseChoice::Retry => self.restart()?,
+            PauseChoice::Back => self.pending = Some(Transition::Pop),
+        }
+        Ok(())
+    }
+
+    fn fail_menu_shown(&self) -> bool {
+        self.state == GameState::Failed && self.fail_screen.progress() >= 1.0
+    }
+
+    // the stage, notes and HUD, everything Cinema hides
+    fn draw_playfield(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+
+        window.draw_ex(
+            &Rectangle::new((0, 0), (w, h)),
+            Col(Color::BLACK
+                .with_alpha(BACKGROUND_DIM * (1.0 - self.breaks.dim_lift(self.clock.position())))),
+            Transform::IDENTITY,
+            -1,
+        );
+
+        let position = self.clock.position();
+        let scale = h / SKIN_HEIGHT;
+        let scroll_speed = self.scroll_speed;
+        let timeline = &self.timeline;
+        let current_distance = scroll_speed.distance(timeline, position);
+        let pixels_per_unit =
+            scroll_speed.pixels_per_unit(timeline, scale, self.layout.hit_position);
+        let scroll = |time: f32| {
+            (scroll_speed.distance(timeline, time) - current_distance) * pixels_per_unit
+        };
+        let stage_x = (w - SKIN_WIDTH * scale) / 2.0;
+        let hit_line = h - self.layout.hit_position * scale;
+        let mods = self.mods;
+        let stage_height = self.layout.hit_position * scale;
+        let opacity = |distance: f32| mods.note_opacity(distance / stage_height);
+        let edges = self.layout.column_edges();
+        let column_width = &self.layout.column_width;
+        self.lanes.iter_mut().enumerate().for_each(|(i, lane)| {
+            lane.draw(
+                window,
+                &Rectangle::new(
+                    (stage_x + edges[i] * scale, 0),
+                    (column_width[i] * scale, h),
+                ),
+                position,
+                &scroll,
+                &opacity,
+                hit_line,
+            )
+        });
+
+        for (edge, line_width) in edges.iter().zip(&self.layout.column_line_width) {
+            if *line_width <= 0.0 {
+                continue;
+            }
+            let x = stage_x + edge * scale;
+            window.draw(
+                &Line::new((x, 0), (x, h - hit_line)).with_thickness(line_width * scale),
+                Col(Color::from_rgba(255, 255, 255, 0.4)),
+            );
+        }
+
+        self.hit_score
+            .draw(window, Vector::new(w / 2.0, h / 2.0), position);
+        if let Some(countdown) = &mut self.countdown {
+            countdown.draw(window, Vector::new(w / 2.0, h / 2.0), position)?;
+        }
+        self.breaks.draw(window, position)?;
+        self.health.draw(window);
+
+        self.number.draw(
+            window,
+            &Vector::new(0, 0),
+            &Vector::new(5, 5),
+            window.current_fps() as u32,
+        );
+        self.number.draw(
+            window,
+            &Vector::new(0, 200),
+            &Vector::new(5, 5),
+            self.score.score(),
+        );
+        self.number.draw_str(
+            window,
+            &Vector::new(0, 260),
+            &format!("{:.2}%", self.score.accuracy() * 100.0),
+        );
+        if self.score.combo() > 0 {
+            self.combo_number.draw_str(
+                window,
+                &Vector::new(w / 2.0, h / 3.0),
+                &format!("{}x", self.score.combo()),
+            );
+        }
+
+        self.mod_icons
+            .draw(window, self.mods, Vector::new(w - 10.0, 10.0))?;
+
+        let scroll_text = format!("Scroll speed {} (F3/F4)", self.scroll_speed);
+        self.font.execute(|font| {
+            let text = font.render(&scroll_text, &FontStyle::new(20.0, Color::WHITE))?;
+            window.draw_ex(
+                &text.area().translate((10.0, h - text.area().size.y - 10.0)),
+                Img(&text),
+                Transform::IDENTITY,
+                6,
+            );
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Scene for Camera {
+    fn update(&mut self, _window: &mut Window) -> Result<Transition> {
+        if let Some(transition) = self.pending.take() {
+            return Ok(transition);
+        }
+        if self.restart_progress() >= 1.0 {
+            self.restart()?;
+        }
+
+        match self.state {
+            GameState::Playing => {
+                if !self.clock.is_started() {
+                    self.clock.play();
+                }
+            }
+            GameState::Paused => return Ok(Transition::None),
+            GameState::Resuming(since) => {
+                if since.elapsed().as_secs_f32() * 1000.0 < RESUME_STEP * RESUME_STEPS as f32 {
+                    return Ok(Transition::None);
+                }
+                self.clock.play();
+                self.state = GameState::Playing;
+            }
+            GameState::Failed => {
+                let progress = self.fail_screen.progress();
+                self.clock.set_volume(1.0 - progress);
+                if progress >= 1.0 {
+                    self.clock.pause();
+                }
+                return Ok(Transition::None);
+            }
+        }
+
+        self.clock.update();
+
+        let position = self.clock.position();
+        if let Some(nightcore) = &mut self.nightcore {
+            nightcore.update(position)?;
+        }
+        if let Some(countdown) = &mut self.countdown {
+            countdown.update(position)?;
+        }
+        let events = match &mut self.playback {
+            Some(playback) => playback.events_until(position, &self.hotkeys),
+            None => {
+                let events = self.input.drain(&self.clock);
+                self.replay.record(&events, &self.hotkeys);
+                events
+            }
+        };
+        let judgements: Vec<Judgement> = self
+            .lanes
+            .iter_mut()
+            .flat_map(|lane| lane.update(&events, position))
+            .collect();
+        for (lane, samples) in self.lanes.iter_mut().zip(&self.lane_samples) {
+            for index in lane.drain_sounds() {
+                self.hitsounds.play(&samples[index]);
+            }
+        }
+        let mut failed = false;
+        for judgement in judgements {
+            self.hit_score.play(judgement.result, position);
+            self.score.apply(judgement);
+            self.health.apply(judgement.result);
+            failed |= self.mods.fails_on(judgement.result);
+        }
+        if self
+            .life_bar
+            .last()
+            .map_or(true, |(last, _)| position - last >= LIFE_BAR_INTERVAL)
+        {
+            self.life_bar.push((position, self.health.hp()));
+        }
+        if self.mods.can_fail() && (failed || self.health.is_failed()) {
+            self.state = GameState::Failed;
+            self.fail_screen.start();
+            self.pause_menu.open(true);
+        }
+
+        self.hit_score.update(position);
+        self.breaks.update(
+            position,
+            self.health.hp() >= SECTION_PASS_HP && self.score.accuracy() >= SECTION_PASS_ACCURACY,
+        )?;
+
+        if position > self.end_position + END_DELAY
+            && self.lanes.iter().all(|lane| lane.is_finished())
+        {
+            if self.playback.is_none() {
+                let path = self.replay.default_path();
+                self.replay.save(&path)?;
+                let osr_path = path.with_extension("osr");
+                // the .rpl above is enough to watch it here, the .osr is only for osu!
+                if let Err(err) = OsrReplay::new(
+                    &self.replay,
+                    &self.score,
+                    &self.life_bar,
+                    &self.beatmap_hash,
+                )
+                .save(&osr_path)
+                {
+                    eprintln!("Not exporting {}: {:?}", osr_path.display(), err);
+                }
+            }
+            return Ok(Transition::Replace(Box::new(Results::new(
+                &self.score,
+                self.mods,
+            )?)));
+        }
+
+        Ok(Transition::None)
+    }
+
+    fn event(&mut self, event: &Event, _window: &mut Window) -> Result<()> {
+        if let Event::Key(key, state) = event {
+            let paused = self.state == GameState::Paused || self.fail_menu_shown();
+            let skip_target = self.skip_target();
+            match (key, state) {
+                (&RESTART_KEY, ButtonState::Pressed) if self.restart_held.is_none() => {
+                    self.restart_held = Some(Instant::now())
+                }
+                (&RESTART_KEY, ButtonState::Released) => self.restart_held = None,
+                (&RESTART_KEY, _) => (),
+                (_, ButtonState::Pressed) if paused => {
+                    if let Some(choice) = self.pause_menu.press(*key) {
+                        self.choose(choice)?;
+                    }
+                }
+                (Key::Escape, ButtonState::Pressed) if self.state != GameState::Failed => {
+                    self.pause()
+                }
+                (&SKIP_KEY, ButtonState::Pressed) if skip_target.is_some() => {
+                    self.clock.seek(skip_target.unwrap())?
+                }
+                (Key::F3, ButtonState::Pressed) => self.scroll_speed.adjust(-1),
+                (Key::F4, ButtonState::Pressed) => self.scroll_speed.adjust(1),
+                // keys let go while the song is stopped still reach the lanes
+                _ if self.playback.is_none()
+                    && (self.state == GameState::Playing || *state == ButtonState::Released) =>
+                {
+                    self.input.push(*key, *state)
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+
+        if let Some(asset_bg) = &mut self.asset_bg {
+            let drawn = asset_bg.execute(|image| {
+                window.draw_ex(
+                    &image.area().with_center((w / 2.0, h / 2.0)),
+                    Img(&image),
+                    Transform::scale((w / image.area().size.x, h / image.area().size.y)),
+                    -2,
+                );
+                Ok(())
+            });
+            // a background that doesn't load is left out like a missing one
+            if drawn.is_err() {
+                self.asset_bg = None;
+            }
+        }
+        // Cinema only shows the background and whatever needs an answer
+        if !self.mods.contains(Mod::Cinema) {
+            self.draw_playfield(window)?;
+        }
+
+        if self.state == GameState::Failed {
+            self.fail_screen.draw(window);
+        }
+        if self.state == GameState::Paused || self.fail_menu_shown() {
+            self.pause_menu.draw(window)?;
+        }
+        if self.skip_target().is_some() {
+            self.asset_skip.execute(|image| {
+                let size = image.area().size;
+                window.draw_ex(
+                    &image.area().translate((w - size.x, h - size.y)),
+                    Img(&image),
+                    Transform::IDENTITY,
+                    6,
+                );
+                Ok(())
+            })?;
+        }
+        let restart_progress = self.restart_progress();
+        if restart_progress > 0.0 {
+            window.draw_ex(
+                &Rectangle::new((0, 0), (w, h)),
+                Col(Color::BLACK.with_alpha(restart_progress)),
+                Transform::IDENTITY,
+                14,
+            );
+        }
+        if let GameState::Resuming(since) = self.state {
+            let elapsed = since.elapsed().as_secs_f32() * 1000.0;
+            let count = RESUME_STEPS.saturating_sub((elapsed / RESUME_STEP) as u32);
+            self.combo_number.draw_str(
+                window,
+                &Vector::new(w / 2.0, h / 2.0),
+                &count.max(1).to_string(),
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use rodio::{Device, Sample, Sink, Source};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use quicksilver::Result;
+
+use crate::music::{Music, MusicSource};
+use crate::stretch::TimeStretch;
+
+// drift bigger than this means the audio skipped or stalled, so jump straight to it
+const SNAP_THRESHOLD: f64 = 80.0;
+// fraction of the remaining drift removed every update
+const DRIFT_CORRECTION: f64 = 0.05;
+// audio callbacks hand out samples in chunks, don't extrapolate further than one chunk
+const MAX_INTERPOLATION: f64 = 100.0;
+
+struct Tracked<S> {
+    inner: S,
+    played_samples: Arc<AtomicUsize>,
+    // set once the music has run out
+    finished: Arc<AtomicBool>,
+}
+
+impl<S> Iterator for Tracked<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next();
+        if sample.is_some() {
+            self.played_samples.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+        sample
+    }
+}
+
+impl<S> Source for Tracked<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+pub struct SongClock {
+    sink: Sink,
+    played_samples: Arc<AtomicUsize>,
+    finished: Arc<AtomicBool>,
+    // song time covered by every sample handed to the sink
+    ms_per_sample: f64,
+    // song milliseconds per real millisecond
+    rate: f64,
+    music: Arc<Music>,
+    keep_pitch: bool,
+    lead_in: f64,
+    last_played_samples: usize,
+    last_audio_position: f64,
+    last_audio_instant: Instant,
+    last_update: Instant,
+    position: f64,
+    started: bool,
+    paused: bool,
+}
+
+fn output_device() -> Result<Device> {
+    rodio::default_output_device().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "No audio output device").into()
+    })
+}
+
+impl SongClock {
+    // `keep_pitch` time-stretches the music, otherwise it's resampled and the pitch goes up
+    // with the rate like Nightcore
+    pub fn new(music_path: &str, rate: f32, keep_pitch: bool) -> Result<SongClock> {
+        let music = Music::decode(music_path)?;
+        SongClock::open(music, rate, keep_pitch, 0.0)
+    }
+
+    // the clock counts up to 0 for `lead_in` ms before the music starts
+    pub fn with_lead_in(mut self, lead_in: f32) -> SongClock {
+        self.lead_in = lead_in as f64;
+        self.position = -self.lead_in;
+        self
+    }
+
+    // plays the already decoded `music` from `start` on a new sink
+    fn open(music: Arc<Music>, rate: f32, keep_pitch: bool, start: f32) -> Result<SongClock> {
+        let samples_per_ms = music.sample_rate() as f64 * music.channels() as f64 / 1000.0;
+        // resampling keeps one output sample per song sample, stretching doesn't
+        let ms_per_sample = if rate != 1.0 && keep_pitch {
+            rate as f64 / samples_per_ms
+        } else {
+            1.0 / samples_per_ms
+        };
+        let source = MusicSource::new(music.clone(), (start as f64 * samples_per_ms) as usize);
+        let played_samples = Arc::new(AtomicUsize::new((start as f64 / ms_per_sample) as usize));
+
+        let sink = Sink::new(&output_device()?);
+        sink.pause();
+        let played = played_samples.clone();
+        let finished = Arc::new(AtomicBool::new(false));
+        if rate == 1.0 {
+            sink.append(Tracked {
+                inner: source,
+                played_samples: played,
+                finished: finished.clone(),
+            });
+        } else if keep_pitch {
+            sink.append(Tracked {
+                inner: TimeStretch::new(source, rate),
+                played_samples: played,
+                finished: finished.clone(),
+            });
+        } else {
+            sink.append(Tracked {
+                inner: source.speed(rate),
+                played_samples: played,
+                finished: finished.clone(),
+            });
+        }
+
+        let now = Instant::now();
+        let last_played_samples = played_samples.load(Ordering::Relaxed);
+        Ok(SongClock {
+            sink,
+            played_samples,
+            finished,
+            ms_per_sample,
+            rate: rate as f64,
+            music,
+            keep_pitch,
+            lead_in: 0.0,
+            last_played_samples,
+            last_audio_position: start as f64,
+            last_audio_instant: now,
+            last_update: now,
+            position: start as f64,
+            started: false,
+            paused: false,
+        })
+    }
+
+    // back to the start of the song, waiting for `play` like a new clock, without decoding
+    // the music again
+    pub fn restart(&mut self) -> Result<()> {
+        *self = SongClock::open(self.music.clone(), self.rate as f32, self.keep_pitch, 0.0)?
+            .with_lead_in(self.lead_in as f32);
+        Ok(())
+    }
+
+    // jumps to `position`, carrying on playing if the clock was
+    pub fn seek(&mut self, position: f32) -> Result<()> {
+        let playing = self.started && !self.paused;
+        let lead_in = self.lead_in;
+        *self = SongClock::open(
+            self.music.clone(),
+            self.rate as f32,
+            self.keep_pitch,
+            position.max(0.0),
+        )?;
+        self.lead_in = lead_in;
+        self.position = position as f64;
+        if playing {
+            self.play();
+        }
+        Ok(())
+    }
+
+    pub fn play(&mut self) {
+        let now = Instant::now();
+        self.last_audio_instant = now;
+        self.last_update = now;
+        self.started = true;
+        self.paused = false;
+        // during the lead-in the music waits for `update` to reach 0
+        if self.position >= 0.0 {
+            self.sink.play();
+        }
+    }
+
+    // the position stays where it is until `play`
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.sink.pause();
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.sink.set_volume(volume);
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_update).as_secs_f64() * 1000.0 * self.rate;
+        self.last_update = now;
+        if !self.started || self.paused {
+            return;
+        }
+        // nothing left to follow once the music ends, the map may still go on for a bit
+        if self.finished.load(Ordering::Relaxed) {
+            self.position += elapsed;
+            return;
+        }
+        if self.position < 0.0 {
+            self.position += elapsed;
+            if self.position >= 0.0 {
+                self.last_audio_instant = now;
+                self.sink.play();
+            }
+            return;
+        }
+
+        let played_samples = self.played_samples.load(Ordering::Relaxed);
+        if played_samples != self.last_played_samples {
+            self.last_played_samples = played_samples;
+            self.last_audio_position = played_samples as f64 * self.ms_per_sample;
+            self.last_audio_instant = now;
+        }
+        let audio_position = self.last_audio_position
+            + (now.duration_since(self.last_audio_instant).as_secs_f64() * 1000.0)
+                .min(MAX_INTERPOLATION)
+                * self.rate;
+
+        let position = self.position + elapsed;
+        let drift = audio_position - position;
+        self.position = if drift.abs() > SNAP_THRESHOLD {
+            audio_position
+        } else {
+            position + drift * DRIFT_CORRECTION
+        }
+        .max(self.position);
+    }
+
+    pub fn position_at(&self, instant: Instant) -> f32 {
+        if !self.started || self.paused {
+            return self.position as f32;
+        }
+        let offset = if instant > self.last_update {
+            instant.duration_since(self.last_update).as_secs_f64()
+        } else {
+            -self.last_update.duration_since(instant).as_secs_f64()
+        };
+        (self.position + offset * 1000.0 * self.rate) as f32
+    }
+
+    pub fn position(&self) -> f32 {
+        self.position as f32
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use osu_format::HitObject;
+
+// a lane is only reused once the player had time to let go of the previous note
+const MIN_GAP: u32 = 30;
+
+// xorshift, so a replay's seed shuffles the same way every time. osu! uses its own RNG
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Rng {
+        Rng(if seed == 0 { 1 } else { seed })
+    }
+
+    fn next(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+}
+
+pub fn random_seed() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos())
+}
+
+fn span(hit_object: &HitObject) -> (u32, u32) {
+    match hit_object {
+        HitObject::Circle { base } => (base.time, base.time),
+        HitObject::LongNote { base, end_time } => (base.time, *end_time),
+        _ => unreachable!(),
+    }
+}
+
+// Moves every note to the lane at the same relative position in `keys` lanes, or the
+// nearest one that's free at that time. Notes that find no free lane are dropped.
+pub fn convert(lane_maps: Vec<Vec<HitObject>>, keys: usize) -> Vec<Vec<HitObject>> {
+    let old_keys = lane_maps.len();
+    if keys == old_keys || keys == 0 {
+        return lane_maps;
+    }
+    let mut notes: Vec<(usize, HitObject)> = lane_maps
+        .into_iter()
+        .enumerate()
+        .flat_map(|(column, lane_map)| lane_map.into_iter().map(move |note| (column, note)))
+        .collect();
+    // stable, so chords keep their left to right order
+    notes.sort_by_key(|(_, note)| span(note).0);
+
+    let mut converted = vec![Vec::new(); keys];
+    let mut free_from = vec![0; keys];
+    for (column, note) in notes {
+        let (start, end) = span(&note);
+        let target =
+            (((column as f32 + 0.5) * keys as f32 / old_keys as f32) as usize).min(keys - 1);
+        let lane = (0..keys)
+            .filter(|lane| start >= free_from[*lane])
+            .min_by_key(|lane| ((*lane as i32 - target as i32).abs(), *lane));
+        if let Some(lane) = lane {
+            free_from[lane] = end + MIN_GAP;
+            converted[lane].push(note);
+        }
+    }
+    converted
+}
+
+pub fn mirror(mut lane_maps: Vec<Vec<HitObject>>) -> Vec<Vec<HitObject>> {
+    lane_maps.reverse();
+    lane_maps
+}
+
+pub fn shuffle(mut lane_maps: Vec<Vec<HitObject>>, seed: u32) -> Vec<Vec<HitObject>> {
+    let mut rng = Rng::new(seed);
+    for i in (1..lane_maps.len()).rev() {
+        let j = rng.next() as usize % (i + 1);
+        lane_maps.swap(i, j);
+    }
+    lane_maps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osu_format::{HitObjectBase, HitObjectExtras};
+
+    fn base(time: u32) -> HitObjectBase {
+        HitObjectBase {
+            x: 0,
+            y: 192,
+            time,
+            hit_sound: 0,
+            extras: HitObjectExtras {
+                sample_set: 0,
+                addition_set: 0,
+                custom_index: 0,
+                sample_volume: 0,
+                filename: String::new(),
+            },
+        }
+    }
+
+    fn circle(time: u32) -> HitObject {
+        HitObject::Circle { base: base(time) }
+    }
+
+    fn long_note(time: u32, end_time: u32) -> HitObject {
+        HitObject::LongNote {
+            base: base(time),
+            end_time,
+        }
+    }
+
+    fn spans(lane_maps: &[Vec<HitObject>]) -> Vec<Vec<(u32, u32)>> {
+        lane_maps
+            .iter()
+            .map(|lane_map| lane_map.iter().map(span).collect())
+            .collect()
+    }
+
+    #[test]
+    fn chord_spreads_over_fewer_keys() {
+        let lane_maps = vec![
+            vec![circle(1000)],
+            vec![],
+            vec![circle(1000)],
+            vec![],
+            vec![circle(1000)],
+            vec![],
+            vec![circle(1000)],
+        ];
+        let converted = convert(lane_maps, 4);
+        assert_eq!(
+            spans(&converted),
+            vec![
+                vec![(1000, 1000)],
+                vec![(1000, 1000)],
+                vec![(1000, 1000)],
+                vec![(1000, 1000)],
+            ]
+        );
+    }
+
+    #[test]
+    fn chord_wider_than_keys_is_cut() {
+        let lane_maps = (0..7).map(|_| vec![circle(1000)]).collect();
+        let converted = convert(lane_maps, 4);
+        assert_eq!(converted.len(), 4);
+        assert!(converted.iter().all(|lane_map| lane_map.len() == 1));
+    }
+
+    #[test]
+    fn long_note_holds_its_lane() {
+        let lane_maps = vec![
+            vec![long_note(0, 1000), circle(1020)],
+            vec![circle(500)],
+            vec![circle(510)],
+            vec![],
+        ];
+        let converted = convert(lane_maps, 2);
+        // the circle at 510 has nowhere to go while the long note and the circle at 500
+        // hold both lanes, the one at 1020 moves over as the tail is too close
+        assert_eq!(
+            spans(&converted),
+            vec![vec![(0, 1000)], vec![(500, 500), (1020, 1020)]]
+        );
+    }
+
+    #[test]
+    fn same_keys_is_unchanged() {
+        let lane_maps = vec![vec![circle(0), long_note(100, 300)], vec![circle(50)]];
+        let converted = convert(lane_maps, 2);
+        assert_eq!(
+            spans(&converted),
+            vec![vec![(0, 0), (100, 300)], vec![(50, 50)]]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use osu_format::TimingPoint;
+use quicksilver::{
+    geom::{Shape, Transform, Vector},
+    graphics::{Background::Blended, Color, Image},
+    lifecycle::{Asset, Window},
+    sound::Sound,
+    Result,
+};
+
+// ready, 3, 2, 1 and go, one beat apart
+const STEPS: [(&str, &str); 5] = [
+    ("static/skin/ready.png", "static/skin/readys.wav"),
+    ("static/skin/count3.png", "static/skin/count3s.wav"),
+    ("static/skin/count2.png", "static/skin/count2s.wav"),
+    ("static/skin/count1.png", "static/skin/count1s.wav"),
+    ("static/skin/Go.png", "static/skin/gos.wav"),
+];
+// cues further behind than this are skipped instead of played late
+const MAX_LATENESS: f32 = 50.0;
+
+// The countdown before the first note, `countdown` in the beatmap's [General]
+pub struct Countdown {
+    // time of the first cue
+    start: f32,
+    beat_length: f32,
+    next: usize,
+    images: Vec<Asset<Image>>,
+    sounds: Vec<Asset<Sound>>,
+}
+
+impl Countdown {
+    // 0 is no countdown, 1 normal, 2 half and 3 double speed
+    pub fn new(
+        countdown: u32,
+        timing_points: &[TimingPoint],
+        first_note: f32,
+    ) -> Option<Countdown> {
+        let speed = match countdown {
+            1 => 1.0,
+            2 => 0.5,
+            3 => 2.0,
+            _ => return None,
+        };
+        let red_line = timing_points
+            .iter()
+            .filter(|timing_point| timing_point.milliseconds_per_beat > 0.0)
+            .min_by_key(|timing_point| timing_point.offset)?;
+        let beat_length = red_line.milliseconds_per_beat / speed;
+        // go lands on the last beat at least a beat before the first note
+        let offset = red_line.offset as f32;
+        let go = offset + ((first_note - beat_length - offset) / beat_length).floor() * beat_length;
+        Some(Countdown {
+            start: go - (STEPS.len() - 1) as f32 * beat_length,
+            beat_length,
+            next: 0,
+            images: STEPS
+                .iter()
+                .map(|(image, _)| Asset::new(Image::load(*image)))
+                .collect(),
+            sounds: STEPS
+                .iter()
+                .map(|(_, sound)| Asset::new(Sound::load(*sound)))
+                .collect(),
+        })
+    }
+
+    pub fn start(&self) -> f32 {
+        self.start
+    }
+
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    pub fn update(&mut self, position: f32) -> Result<()> {
+        while self.next < STEPS.len() {
+            let time = self.start + self.next as f32 * self.beat_length;
+            if time > position {
+                break;
+            }
+            let step = self.next;
+            self.next += 1;
+            if position - time > MAX_LATENESS {
+                continue;
+            }
+            self.sounds[step].execute(|sound| sound.play())?;
+        }
+        Ok(())
+    }
+
+    // every step stays up for its beat and fades out
+    pub fn draw(&mut self, window: &mut Window, center: Vector, position: f32) -> Result<()> {
+        let beats = (position - self.start) / self.beat_length;
+        if beats < 0.0 || beats >= STEPS.len() as f32 {
+            return Ok(());
+        }
+        let alpha = 1.0 - beats.fract();
+        self.images[beats as usize].execute(|image| {
+            window.draw_ex(
+                &image.area().with_center(center),
+                Blended(&image, Color::WHITE.with_alpha(alpha)),
+                Transform::IDENTITY,
+                6,
+            );
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Blended, Img},
+        Color, Image,
+    },
+    lifecycle::{Asset, Window},
+    sound::Sound,
+    Result,
+};
+use std::time::Instant;
+
+const FAIL_DURATION: f32 = 2000.0;
+
+pub struct FailScreen {
+    started: Option<Instant>,
+    asset_background: Asset<Image>,
+    asset_section_fail: Asset<Image>,
+    asset_sound: Asset<Sound>,
+}
+
+impl FailScreen {
+    pub fn new() -> Result<FailScreen> {
+        Ok(FailScreen {
+            started: None,
+            asset_background: Asset::new(Image::load("static/skin/fail-background.png")),
+            asset_section_fail: Asset::new(Image::load("static/skin/section-fail.png")),
+            asset_sound: Asset::new(Sound::load("static/skin/sectionfail.mp3")),
+        })
+    }
+
+    pub fn start(&mut self) {
+        self.started = Some(Instant::now());
+        self.asset_sound
+            .execute(|sound| sound.play())
+            .expect("Failed to play fail sound");
+    }
+
+    pub fn reset(&mut self) {
+        self.started = None;
+    }
+
+    // goes from 0 to 1 over the course of the fail animation
+    pub fn progress(&self) -> f32 {
+        self.started.map_or(0.0, |started| {
+            (started.elapsed().as_secs_f32() * 1000.0 / FAIL_DURATION).min(1.0)
+        })
+    }
+
+    pub fn draw(&mut self, window: &mut Window) {
+        let Vector { x: w, y: h } = window.screen_size();
+        let progress = self.progress();
+        self.asset_background
+            .execute(|image| {
+                window.draw_ex(
+                    &Rectangle::new((0, 0), (w, h)),
+                    Blended(&image, Color::WHITE.with_alpha(progress)),
+                    Transform::IDENTITY,
+                    10,
+                );
+                Ok(())
+            })
+            .expect("Failed to draw fail screen");
+        self.asset_section_fail
+            .execute(|image| {
+                window.draw_ex(
+                    &image.area().with_center((w / 2.0, h / 2.0)),
+                    Img(&image),
+                    Transform::IDENTITY,
+                    11,
+                );
+                Ok(())
+            })
+            .expect("Failed to draw fail screen");
+    }
+}
@@ -0,0 +1,113 @@
+use crate::hit_score::HitResult;
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{Background::Img, Image},
+    lifecycle::{Asset, Window},
+    Result,
+};
+
+// same shape as osu!'s difficulty range: `min` at HP 0, `mid` at HP 5, `max` at HP 10
+fn difficulty_range(hp_drain_rate: f32, min: f32, mid: f32, max: f32) -> f32 {
+    if hp_drain_rate > 5.0 {
+        mid + (max - mid) * (hp_drain_rate - 5.0) / 5.0
+    } else {
+        mid - (mid - min) * (5.0 - hp_drain_rate) / 5.0
+    }
+}
+
+pub struct Health {
+    hp: f32,
+    hp_drain_rate: f32,
+    asset_bg: Asset<Image>,
+    asset_colour: Asset<Image>,
+    asset_ki: Asset<Image>,
+    asset_ki_danger: Asset<Image>,
+    asset_ki_danger2: Asset<Image>,
+}
+
+impl Health {
+    pub fn new(hp_drain_rate: f32) -> Result<Health> {
+        Ok(Health {
+            hp: 1.0,
+            hp_drain_rate,
+            asset_bg: Asset::new(Image::load("static/skin/scorebar-bg.png")),
+            asset_colour: Asset::new(Image::load("static/skin/scorebar-colour.png")),
+            asset_ki: Asset::new(Image::load("static/skin/scorebar-ki.png")),
+            asset_ki_danger: Asset::new(Image::load("static/skin/scorebar-kidanger.png")),
+            asset_ki_danger2: Asset::new(Image::load("static/skin/scorebar-kidanger2.png")),
+        })
+    }
+
+    fn change_for(&self, result: HitResult) -> f32 {
+        let hp = self.hp_drain_rate;
+        match result {
+            HitResult::Hit300g => difficulty_range(hp, 0.012, 0.008, 0.004),
+            HitResult::Hit300 => difficulty_range(hp, 0.010, 0.006, 0.002),
+            HitResult::Hit200 => difficulty_range(hp, 0.004, 0.002, 0.0),
+            HitResult::Hit100 => 0.0,
+            HitResult::Hit50 => -difficulty_range(hp, 0.008, 0.016, 0.024),
+            HitResult::Miss => -difficulty_range(hp, 0.04, 0.08, 0.12),
+            HitResult::NoHit => 0.0,
+        }
+    }
+
+    pub fn apply(&mut self, result: HitResult) {
+        self.hp = (self.hp + self.change_for(result)).clamp(0.0, 1.0);
+    }
+
+    pub fn reset(&mut self) {
+        self.hp = 1.0;
+    }
+
+    pub fn hp(&self) -> f32 {
+        self.hp
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.hp <= 0.0
+    }
+
+    pub fn draw(&mut self, window: &mut Window) {
+        self.asset_bg
+            .execute(|image| {
+                window.draw_ex(&image.area(), Img(&image), Transform::IDENTITY, 6);
+                Ok(())
+            })
+            .expect("Failed to draw scorebar");
+
+        let hp = self.hp;
+        let mut bar_end = Vector::new(0, 0);
+        self.asset_colour
+            .execute(|image| {
+                let size = image.area().size;
+                let visible = image.subimage(Rectangle::new((0, 0), (size.x * hp, size.y)));
+                window.draw_ex(
+                    &Rectangle::new((0, 0), (size.x * hp, size.y)),
+                    Img(&visible),
+                    Transform::IDENTITY,
+                    7,
+                );
+                bar_end = Vector::new(size.x * hp, size.y / 2.0);
+                Ok(())
+            })
+            .expect("Failed to draw scorebar");
+
+        let ki = if hp < 0.2 {
+            &mut self.asset_ki_danger2
+        } else if hp < 0.5 {
+            &mut self.asset_ki_danger
+        } else {
+            &mut self.asset_ki
+        };
+        ki.execute(|image| {
+            window.draw_ex(
+                &image.area().with_center(bar_end),
+                Img(&image),
+                Transform::IDENTITY,
+                8,
+            );
+            Ok(())
+        })
+        .expect("Failed to draw scorebar");
+    }
+}
@@ -5,13 +5,15 @@ use quicksilver::{
     Result,
 };
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
 pub enum HitResult {
     NoHit,
     Miss,
     Hit50,
     Hit100,
+    Hit200,
     Hit300,
+    Hit300g,
 }
 
 pub struct HitScore {
@@ -19,13 +21,19 @@ pub struct HitScore {
     asset_miss: Asset<Image>,
     asset_hit50: Asset<Image>,
     asset_hit100: Asset<Image>,
+    asset_hit200: Asset<Image>,
     asset_hit300: Asset<Image>,
+    asset_hit300g: Vec<Asset<Image>>,
     animation_duration: f32,
 }
 
 impl HitScore {
     pub fn new() -> Result<HitScore> {
+        let asset_hit300g = (0..2)
+            .map(|frame| Asset::new(Image::load(format!("static/skin/hit300g-{}.png", frame))))
+            .collect();
         let asset_hit300 = Asset::new(Image::load("static/skin/hit300.png"));
+        let asset_hit200 = Asset::new(Image::load("static/skin/hit300k.png"));
         let asset_hit100 = Asset::new(Image::load("static/skin/hit100.png"));
         let asset_hit50 = Asset::new(Image::load("static/skin/hit50.png"));
         let asset_miss = Asset::new(Image::load("static/skin/hit0.png"));
@@ -35,40 +43,49 @@ impl HitScore {
             asset_miss,
             asset_hit50,
             asset_hit100,
+            asset_hit200,
             asset_hit300,
+            asset_hit300g,
             animation_duration: 300.0,
         })
     }
 
-    pub fn play(&mut self, hit_result: HitResult) {
+    pub fn play(&mut self, hit_result: HitResult, position: f32) {
         if let HitResult::NoHit = hit_result {
             return;
         }
-        self.playing = Some((hit_result, self.animation_duration));
+        self.playing = Some((hit_result, position));
     }
 
-    pub fn update(&mut self, window: &mut Window) {
-        if let Some((hit_result, animation_progress)) = self.playing {
-            if window.current_fps() != 0.0 {
-                if animation_progress > 0.0 {
-                    self.playing = Some((
-                        hit_result,
-                        animation_progress - 1000.0 / window.current_fps() as f32,
-                    ));
-                } else {
-                    self.playing = None;
-                }
+    pub fn reset(&mut self) {
+        self.playing = None;
+    }
+
+    pub fn update(&mut self, position: f32) {
+        if let Some((_, start_position)) = self.playing {
+            if position - start_position > self.animation_duration {
+                self.playing = None;
             }
         }
     }
 
-    pub fn draw(&mut self, window: &mut Window, center: Vector) {
-        if let Some((hit_result, animation_progress)) = self.playing {
+    pub fn draw(&mut self, window: &mut Window, center: Vector, position: f32) {
+        if let Some((hit_result, start_position)) = self.playing {
+            let animation_progress =
+                (self.animation_duration - (position - start_position)).max(0.0);
+            let frame_count = self.asset_hit300g.len();
             let asset = match hit_result {
                 HitResult::Miss => &mut self.asset_miss,
                 HitResult::Hit50 => &mut self.asset_hit50,
                 HitResult::Hit100 => &mut self.asset_hit100,
+                HitResult::Hit200 => &mut self.asset_hit200,
                 HitResult::Hit300 => &mut self.asset_hit300,
+                HitResult::Hit300g => {
+                    let frame = ((position - start_position)
+                        / (self.animation_duration / frame_count as f32))
+                        as usize;
+                    &mut self.asset_hit300g[frame.min(frame_count - 1)]
+                }
                 _ => unreachable!(),
             };
             let animation_duration = self.animation_duration;
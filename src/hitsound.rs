@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use osu_format::{HitObjectBase, TimingPoint};
+use quicksilver::{lifecycle::Asset, sound::Sound};
+
+const SKIN_DIRECTORY: &str = "static/skin";
+const SAMPLE_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+// osu! picks the timing point slightly after the object so samples on a line use it
+const TIMING_POINT_LENIENCY: u32 = 5;
+
+const HIT_WHISTLE: u32 = 2;
+const HIT_FINISH: u32 = 4;
+const HIT_CLAP: u32 = 8;
+
+#[derive(Clone, Debug)]
+pub struct Sample {
+    path: PathBuf,
+    volume: f32,
+}
+
+fn sample_set_name(sample_set: u32) -> Option<&'static str> {
+    match sample_set {
+        1 => Some("normal"),
+        2 => Some("soft"),
+        3 => Some("drum"),
+        _ => None,
+    }
+}
+
+// the [General] SampleSet of the beatmap, used when timing points leave it on auto
+pub fn default_sample_set(name: &str) -> u32 {
+    match name.to_lowercase().as_str() {
+        "soft" => 2,
+        "drum" => 3,
+        _ => 1,
+    }
+}
+
+fn find_with_extension(directory: &Path, name: &str) -> Option<PathBuf> {
+    SAMPLE_EXTENSIONS
+        .iter()
+        .map(|extension| directory.join(format!("{}.{}", name, extension)))
+        .find(|path| path.exists())
+}
+
+// Custom indices other than 0 are looked up in the beatmap folder first, then in the skin
+fn find_sample(name: &str, index: u32, beatmap_directory: &Path) -> Option<PathBuf> {
+    let indexed_name = if index > 1 {
+        format!("{}{}", name, index)
+    } else {
+        name.to_string()
+    };
+    let skin = Path::new(SKIN_DIRECTORY);
+    if index != 0 {
+        if let Some(path) = find_with_extension(beatmap_directory, &indexed_name) {
+            return Some(path);
+        }
+    }
+    find_with_extension(skin, &indexed_name).or_else(|| find_with_extension(skin, name))
+}
+
+fn active_timing_point(timing_points: &[TimingPoint], time: u32) -> Option<&TimingPoint> {
+    timing_points
+        .iter()
+        .filter(|timing_point| timing_point.offset <= time + TIMING_POINT_LENIENCY)
+        .max_by_key(|timing_point| timing_point.offset)
+        .or_else(|| {
+            timing_points
+                .iter()
+                .min_by_key(|timing_point| timing_point.offset)
+        })
+}
+
+pub fn resolve(
+    base: &HitObjectBase,
+    timing_points: &[TimingPoint],
+    default_set: u32,
+    beatmap_directory: &Path,
+) -> Vec<Sample> {
+    let extras = &base.extras;
+    let timing_point = active_timing_point(timing_points, base.time);
+    let volume = match extras.sample_volume {
+        0 => timing_point.map_or(100, |timing_point| timing_point.volume),
+        volume => volume,
+    } as f32
+        / 100.0;
+
+    // a keysound replaces every other sample of the object
+    if !extras.filename.is_empty() {
+        let path = beatmap_directory.join(&extras.filename);
+        if path.exists() {
+            return vec![Sample { path, volume }];
+        }
+    }
+
+    let sample_set = sample_set_name(extras.sample_set)
+        .or_else(|| timing_point.and_then(|timing_point| sample_set_name(timing_point.sample_set)))
+        .or_else(|| sample_set_name(default_set))
+        .unwrap_or("normal");
+    let addition_set = sample_set_name(extras.addition_set).unwrap_or(sample_set);
+    let index = match extras.custom_index {
+        0 => timing_point.map_or(0, |timing_point| timing_point.sample_index),
+        index => index,
+    };
+
+    let mut names = vec![format!("{}-hitnormal", sample_set)];
+    for (flag, addition) in &[
+        (HIT_WHISTLE, "hitwhistle"),
+        (HIT_FINISH, "hitfinish"),
+        (HIT_CLAP, "hitclap"),
+    ] {
+        if base.hit_sound & flag != 0 {
+            names.push(format!("{}-{}", addition_set, addition));
+        }
+    }
+    names
+        .iter()
+        .filter_map(|name| find_sample(name, index, beatmap_directory))
+        .map(|path| Sample { path, volume })
+        .collect()
+}
+
+pub struct Hitsounds {
+    sounds: HashMap<PathBuf, Asset<Sound>>,
+}
+
+impl Hitsounds {
+    pub fn new<'a>(samples: impl Iterator<Item = &'a Sample>) -> Hitsounds {
+        let mut sounds = HashMap::new();
+        for sample in samples {
+            sounds
+                .entry(sample.path.clone())
+                .or_insert_with(|| Asset::new(Sound::load(sample.path.clone())));
+        }
+        Hitsounds { sounds }
+    }
+
+    // maps often ship empty files to mute a hitsound, a sample that fails to load is dropped
+    // rather than ending the play
+    pub fn play(&mut self, samples: &[Sample]) {
+        for sample in samples {
+            if let Some(asset) = self.sounds.get_mut(&sample.path) {
+                let played = asset.execute(|sound| {
+                    sound.set_volume(sample.volume);
+                    sound.play()
+                });
+                if let Err(err) = played {
+                    eprintln!("Dropping hitsound {}: {:?}", sample.path.display(), err);
+                    self.sounds.remove(&sample.path);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::clock::SongClock;
+use quicksilver::input::{ButtonState, Key};
+use std::time::Instant;
+
+#[derive(Copy, Clone)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub pressed: bool,
+    pub position: f32,
+}
+
+pub struct InputQueue {
+    events: Vec<(Key, bool, Instant)>,
+    // position the lanes were last updated to, events can't go back before it
+    // or replays would judge them differently
+    judged_position: f32,
+}
+
+impl Default for InputQueue {
+    fn default() -> InputQueue {
+        InputQueue::new()
+    }
+}
+
+impl InputQueue {
+    pub fn new() -> InputQueue {
+        InputQueue {
+            events: Vec::new(),
+            judged_position: f32::MIN,
+        }
+    }
+
+    pub fn push(&mut self, key: Key, state: ButtonState) {
+        let pressed = match state {
+            ButtonState::Pressed => true,
+            ButtonState::Released => false,
+            _ => return,
+        };
+        self.events.push((key, pressed, Instant::now()));
+    }
+
+    // Inputs land on whole milliseconds like in osu! replays, so an exported replay judges
+    // exactly the same. One that rounds up past the current position waits for the next update.
+    pub fn drain(&mut self, clock: &SongClock) -> Vec<KeyEvent> {
+        let position = clock.position();
+        let mut last_position = self.judged_position.ceil();
+        let mut events = Vec::new();
+        for (key, pressed, instant) in &self.events {
+            let event_position = clock.position_at(*instant).round().max(last_position);
+            if event_position > position {
+                break;
+            }
+            last_position = event_position;
+            events.push(KeyEvent {
+                key: *key,
+                pressed: *pressed,
+                position: event_position,
+            });
+        }
+        self.events.drain(..events.len());
+        self.judged_position = position;
+        events
+    }
+}
@@ -0,0 +1,199 @@
+use crate::hit_score::HitResult;
+
+const HARD_ROCK_SCALE: f32 = 1.4;
+const EASY_SCALE: f32 = 1.4;
+// osu!stable gives the head of a long note a bit of extra leniency for 300g and 300
+const LONG_NOTE_MAX_SCALE: f32 = 1.2;
+const LONG_NOTE_300_SCALE: f32 = 1.1;
+const RELEASE_LENIENCY: f32 = 1.5;
+
+#[derive(Copy, Clone)]
+pub struct Judgement {
+    pub result: HitResult,
+    pub position: f32,
+    // hit time minus note time, negative when early, none for misses
+    pub offset: Option<f32>,
+}
+
+impl Judgement {
+    pub fn new(result: HitResult, position: f32, offset: Option<f32>) -> Judgement {
+        Judgement {
+            result,
+            position,
+            offset,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct HitWindows {
+    max: f32,
+    hit300: f32,
+    hit200: f32,
+    hit100: f32,
+    hit50: f32,
+    miss: f32,
+}
+
+impl HitWindows {
+    pub fn new(od: f32) -> HitWindows {
+        HitWindows {
+            max: 16.0,
+            hit300: 64.0 - 3.0 * od,
+            hit200: 97.0 - 3.0 * od,
+            hit100: 127.0 - 3.0 * od,
+            hit50: 151.0 - 3.0 * od,
+            miss: 188.0 - 3.0 * od,
+        }
+    }
+
+    fn scale(self, factor: f32) -> HitWindows {
+        HitWindows {
+            max: self.max * factor,
+            hit300: self.hit300 * factor,
+            hit200: self.hit200 * factor,
+            hit100: self.hit100 * factor,
+            hit50: self.hit50 * factor,
+            miss: self.miss * factor,
+        }
+    }
+
+    pub fn hard_rock(self) -> HitWindows {
+        self.scale(1.0 / HARD_ROCK_SCALE)
+    }
+
+    pub fn easy(self) -> HitWindows {
+        self.scale(EASY_SCALE)
+    }
+
+    // windows are measured in song time, a faster song needs wider windows to feel the same
+    pub fn rate(self, rate: f32) -> HitWindows {
+        self.scale(rate)
+    }
+
+    pub fn miss(&self) -> f32 {
+        self.miss
+    }
+
+    pub fn release(&self) -> f32 {
+        self.hit50 * RELEASE_LENIENCY
+    }
+
+    pub fn result(&self, distance: f32) -> HitResult {
+        let distance = distance.abs();
+        if distance <= self.max {
+            HitResult::Hit300g
+        } else if distance <= self.hit300 {
+            HitResult::Hit300
+        } else if distance <= self.hit200 {
+            HitResult::Hit200
+        } else if distance <= self.hit100 {
+            HitResult::Hit100
+        } else if distance <= self.hit50 {
+            HitResult::Hit50
+        } else {
+            HitResult::Miss
+        }
+    }
+
+    pub fn long_note_result(&self, head_distance: f32, tail_distance: f32) -> HitResult {
+        let head = head_distance.abs();
+        let total = head + tail_distance.abs().min(self.release());
+        if head <= self.max * LONG_NOTE_MAX_SCALE && total <= self.max * LONG_NOTE_MAX_SCALE * 2.0 {
+            HitResult::Hit300g
+        } else if head <= self.hit300 * LONG_NOTE_300_SCALE
+            && total <= self.hit300 * LONG_NOTE_300_SCALE * 2.0
+        {
+            HitResult::Hit300
+        } else if head <= self.hit200 && total <= self.hit200 * 2.0 {
+            HitResult::Hit200
+        } else if head <= self.hit100 && total <= self.hit100 * 2.0 {
+            HitResult::Hit100
+        } else {
+            HitResult::Hit50
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the last result inside the window and the first one past it
+    fn edge(windows: HitWindows, window: f32, inside: HitResult, outside: HitResult) {
+        assert!(
+            windows.result(window) == inside,
+            "{} should be inside",
+            window
+        );
+        assert!(
+            windows.result(-window) == inside,
+            "-{} should be inside",
+            window
+        );
+        assert!(
+            windows.result(window + 0.5) == outside,
+            "{} should be outside",
+            window + 0.5
+        );
+    }
+
+    #[test]
+    fn od_0_windows() {
+        let windows = HitWindows::new(0.0);
+        edge(windows, 16.0, HitResult::Hit300g, HitResult::Hit300);
+        edge(windows, 64.0, HitResult::Hit300, HitResult::Hit200);
+        edge(windows, 97.0, HitResult::Hit200, HitResult::Hit100);
+        edge(windows, 127.0, HitResult::Hit100, HitResult::Hit50);
+        edge(windows, 151.0, HitResult::Hit50, HitResult::Miss);
+        assert_eq!(windows.miss(), 188.0);
+    }
+
+    #[test]
+    fn od_10_windows() {
+        let windows = HitWindows::new(10.0);
+        edge(windows, 16.0, HitResult::Hit300g, HitResult::Hit300);
+        edge(windows, 34.0, HitResult::Hit300, HitResult::Hit200);
+        edge(windows, 67.0, HitResult::Hit200, HitResult::Hit100);
+        edge(windows, 97.0, HitResult::Hit100, HitResult::Hit50);
+        edge(windows, 121.0, HitResult::Hit50, HitResult::Miss);
+        assert_eq!(windows.miss(), 158.0);
+    }
+
+    #[test]
+    fn hard_rock_narrows() {
+        let windows = HitWindows::new(10.0).hard_rock();
+        // 34 / 1.4 and 121 / 1.4
+        edge(windows, 24.0, HitResult::Hit300, HitResult::Hit200);
+        edge(windows, 86.0, HitResult::Hit50, HitResult::Miss);
+    }
+
+    #[test]
+    fn easy_widens() {
+        let windows = HitWindows::new(0.0).easy();
+        // 64 * 1.4 and 151 * 1.4
+        edge(windows, 89.5, HitResult::Hit300, HitResult::Hit200);
+        edge(windows, 211.0, HitResult::Hit50, HitResult::Miss);
+    }
+
+    #[test]
+    fn double_time_widens_in_song_time() {
+        let windows = HitWindows::new(10.0).rate(1.5);
+        edge(windows, 24.0, HitResult::Hit300g, HitResult::Hit300);
+        edge(windows, 51.0, HitResult::Hit300, HitResult::Hit200);
+        edge(windows, 181.5, HitResult::Hit50, HitResult::Miss);
+    }
+
+    #[test]
+    fn long_note_needs_both_ends() {
+        let windows = HitWindows::new(0.0);
+        assert!(windows.long_note_result(0.0, 0.0) == HitResult::Hit300g);
+        // the head gets a bit of leniency, 16 * 1.2
+        assert!(windows.long_note_result(19.0, 0.0) == HitResult::Hit300g);
+        // and the two together get twice that
+        assert!(windows.long_note_result(19.0, 19.0) == HitResult::Hit300g);
+        assert!(windows.long_note_result(19.0, 20.0) == HitResult::Hit300);
+        // a tail let go way too late only costs up to the release window, 151 * 1.5
+        assert!(windows.long_note_result(0.0, 10_000.0) == HitResult::Hit100);
+    }
+}
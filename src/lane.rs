@@ -1,9 +1,11 @@
 use crate::hit_score::HitResult;
+use crate::input::KeyEvent;
+use crate::judgement::{HitWindows, Judgement};
 use osu_format::HitObject;
 use quicksilver::{
-    geom::{Rectangle, Shape, Transform, Vector},
+    geom::{Rectangle, Shape, Transform},
     graphics::{
-        Background::{Col, Img},
+        Background::{Blended, Col, Img},
         Color, Image,
     },
     input::Key,
@@ -11,16 +13,10 @@ use quicksilver::{
     Result,
 };
 
-fn distance_to_hit_result(od: f32, distance: u32) -> HitResult {
-    if (distance as f64) < (50.0 + 30.0 * (5.0 - od) / 5.0) as f64 {
-        HitResult::Hit300
-    } else if (distance as f64) < (100.0 + 40.0 * (5.0 - od) / 5.0) as f64 {
-        HitResult::Hit100
-    } else if (distance as f64) < (150.0 + 50.0 * (5.0 - od) / 5.0) as f64 {
-        HitResult::Hit50
-    } else {
-        HitResult::Miss
-    }
+#[derive(Copy, Clone)]
+struct Hold {
+    head_distance: f32,
+    broken: bool,
 }
 
 pub enum LaneSkin {
@@ -39,11 +35,19 @@ pub struct Lane {
     hotkey: Key,
     map: Vec<HitObject>,
     lowest_index: usize,
-    od: f32,
+    hold: Option<Hold>,
+    windows: HitWindows,
+    // indices of the hit objects whose samples should play, one per key press
+    sounds: Vec<usize>,
 }
 
 impl Lane {
-    pub fn new(lane_skin: LaneSkin, lane_map: &[HitObject], hotkey: Key, od: f32) -> Result<Lane> {
+    pub fn new(
+        lane_skin: LaneSkin,
+        lane_map: &[HitObject],
+        hotkey: Key,
+        windows: HitWindows,
+    ) -> Result<Lane> {
         let lane_skin_suffix = match lane_skin {
             LaneSkin::Lane1 => "1",
             LaneSkin::Lane2 => "2",
@@ -80,88 +84,186 @@ impl Lane {
             hotkey,
             map: lane_map.to_vec(),
             lowest_index: 0,
-            od,
+            hold: None,
+            windows,
+            sounds: Vec::new(),
         })
     }
 
-    pub fn update(&mut self, window: &mut Window, position: f32) -> HitResult {
-        let is_pressed = self.is_pressed;
-        let hotkey = self.hotkey;
+    // `released` is false for a tail held past its window, judged on whatever frame noticed
+    // it, so it has no timing to go into the hit errors
+    fn judge_release(&mut self, position: f32, released: bool) -> Judgement {
+        let hold = self.hold.take().unwrap();
+        let end_time = self.end_time();
+        self.lowest_index += 1;
+        if hold.broken {
+            let result = if self.is_pressed {
+                HitResult::Hit50
+            } else {
+                HitResult::Miss
+            };
+            return Judgement::new(result, position, None);
+        }
+        Judgement::new(
+            self.windows
+                .long_note_result(hold.head_distance, end_time - position),
+            position,
+            if released {
+                Some(position - end_time)
+            } else {
+                None
+            },
+        )
+    }
 
-        for i in self.lowest_index..self.map.len() {
-            match &self.map[i] {
-                // TODO calculate max hit distance using formula
-                HitObject::Circle { base } => {
-                    if (base.time as f64) < (position - 200.0) as f64 {
+    fn end_time(&self) -> f32 {
+        match &self.map[self.lowest_index] {
+            HitObject::LongNote { end_time, .. } => *end_time as f32,
+            _ => unreachable!(),
+        }
+    }
+
+    fn check_misses(&mut self, position: f32, judgements: &mut Vec<Judgement>) {
+        loop {
+            if self.hold.is_some() {
+                if position > self.end_time() + self.windows.release() {
+                    let judgement = self.judge_release(position, false);
+                    judgements.push(judgement);
+                    continue;
+                }
+                return;
+            }
+            match self.map.get(self.lowest_index) {
+                Some(HitObject::Circle { base }) => {
+                    if (base.time as f64) < (position - self.windows.miss()) as f64 {
                         self.lowest_index += 1;
-                        return HitResult::Miss;
-                    } else {
-                        break;
+                        judgements.push(Judgement::new(HitResult::Miss, position, None));
+                        continue;
                     }
                 }
-                HitObject::LongNote { end_time, .. } => {
-                    if (*end_time as f64) < (position - 200.0) as f64 {
-                        self.lowest_index += 1;
-                        return HitResult::Miss;
-                    } else {
-                        break;
+                Some(HitObject::LongNote { base, .. }) => {
+                    if (base.time as f64) < (position - self.windows.miss()) as f64 {
+                        self.hold = Some(Hold {
+                            head_distance: self.windows.miss(),
+                            broken: true,
+                        });
+                        judgements.push(Judgement::new(HitResult::Miss, position, None));
+                        continue;
                     }
                 }
-                _ => unreachable!(),
+                Some(_) => unreachable!(),
+                None => (),
             }
+            return;
         }
+    }
 
-        if window.keyboard()[hotkey].is_down() {
-            if !is_pressed {
-                self.is_pressed = true;
-                let mut last_distance: Option<u32> = None;
-                for i in self.lowest_index..self.map.len() {
-                    let hit_object = &self.map[i];
-                    // TODO handle long notes
-                    if let HitObject::Circle { base } = hit_object {
-                        let distance: u32 = ((base.time as i32) - (position as i32)).abs() as u32;
-                        if let Some(last_dist) = last_distance {
-                            if distance < last_dist {
-                                last_distance = Some(distance);
-                            } else {
-                                let result = distance_to_hit_result(self.od, last_dist);
-                                match result {
-                                    HitResult::Miss => return result,
-                                    _ => {
-                                        self.lowest_index += 1;
-                                        return result;
-                                    }
-                                }
-                            }
-                        } else {
-                            last_distance = Some(distance);
-                        }
-                    }
-                }
-                return HitResult::Miss;
-            }
+    // back to the first note, for restarting the map
+    pub fn reset(&mut self) {
+        self.is_pressed = false;
+        self.lowest_index = 0;
+        self.hold = None;
+        self.sounds.clear();
+    }
+
+    fn press(&mut self, position: f32) -> Option<Judgement> {
+        if self.is_pressed {
+            return None;
+        }
+        self.is_pressed = true;
+        if !self.map.is_empty() {
+            self.sounds.push(self.lowest_index.min(self.map.len() - 1));
+        }
+        if self.hold.is_some() {
+            return None;
+        }
+        let (time, is_long_note) = match self.map.get(self.lowest_index)? {
+            HitObject::Circle { base } => (base.time, false),
+            HitObject::LongNote { base, .. } => (base.time, true),
+            _ => unreachable!(),
+        };
+        if (time as f64) > (position + self.windows.miss()) as f64 {
+            return None;
+        }
+        let distance = time as f32 - position;
+        let result = self.windows.result(distance);
+        if is_long_note {
+            self.hold = Some(Hold {
+                head_distance: distance,
+                broken: result == HitResult::Miss,
+            });
         } else {
-            self.is_pressed = false;
+            self.lowest_index += 1;
+        }
+        let offset = match result {
+            HitResult::Miss => None,
+            _ => Some(-distance),
+        };
+        Some(Judgement::new(result, position, offset))
+    }
+
+    fn release(&mut self, position: f32) -> Option<Judgement> {
+        if !self.is_pressed {
+            return None;
         }
-        return HitResult::NoHit;
+        self.is_pressed = false;
+        let hold = self.hold?;
+        if position < self.end_time() - self.windows.release() {
+            self.hold = Some(Hold {
+                broken: true,
+                ..hold
+            });
+        } else if !hold.broken {
+            return Some(self.judge_release(position, true));
+        }
+        None
+    }
+
+    pub fn map(&self) -> &[HitObject] {
+        &self.map
+    }
+
+    pub fn drain_sounds(&mut self) -> Vec<usize> {
+        self.sounds.drain(..).collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.hold.is_none() && self.lowest_index >= self.map.len()
+    }
+
+    pub fn update(&mut self, events: &[KeyEvent], position: f32) -> Vec<Judgement> {
+        let hotkey = self.hotkey;
+        let mut judgements = Vec::new();
+        for event in events.iter().filter(|event| event.key == hotkey) {
+            self.check_misses(event.position, &mut judgements);
+            let judgement = if event.pressed {
+                self.press(event.position)
+            } else {
+                self.release(event.position)
+            };
+            judgements.extend(judgement);
+        }
+        self.check_misses(position, &mut judgements);
+        judgements
     }
 
     pub fn draw(
         &mut self,
         window: &mut Window,
-        pos: &Vector,
-        size: &Vector,
+        area: &Rectangle,
         position: f32,
-        speed: f32,
-        key_height: f32,
+        scroll: &dyn Fn(f32) -> f32,
+        opacity: &dyn Fn(f32) -> f32,
         hit_line: f32,
     ) {
+        let (pos, size) = (&area.pos, &area.size);
         let hit_objects = &mut self.map;
         let lowest_index = self.lowest_index;
-        // TODO make note fall speed and note size somewhat predictable
+        let is_holding = self.hold.map_or(false, |hold| !hold.broken) && self.is_pressed;
         // TODO draw sliders
         if self.is_pressed {
             self.asset_key_down.execute(|key| {
+                let key_height = size.x * key.area().size.y / key.area().size.x;
                 window.draw_ex(
                     &Rectangle::new((pos.x, pos.y + size.y - key_height), (size.x, key_height)),
                     Img(&key),
@@ -172,6 +274,7 @@ impl Lane {
             });
         } else {
             self.asset_key.execute(|key| {
+                let key_height = size.x * key.area().size.y / key.area().size.x;
                 window.draw_ex(
                     &Rectangle::new((pos.x, pos.y + size.y - key_height), (size.x, key_height)),
                     Img(&key),
@@ -193,7 +296,7 @@ impl Lane {
             let hit_object = &hit_objects[i];
             match hit_object {
                 HitObject::Circle { base } => {
-                    if (base.time as f32 - position) * (speed / 100.0) > (size.y + 50.0) {
+                    if scroll(base.time as f32) > (size.y + 50.0) {
                         break;
                     }
                     self.asset_note.execute(|note| {
@@ -201,13 +304,14 @@ impl Lane {
                             &Rectangle::new(
                                 (
                                     pos.x,
-                                    pos.y - hit_line
-                                        + (size.y
-                                            - (base.time as f32 - position) * (speed / 100.0)),
+                                    pos.y - hit_line + (size.y - scroll(base.time as f32)),
                                 ),
-                                (size.x, speed / 4.0),
+                                (size.x, size.x * note.area().size.y / note.area().size.x),
+                            ),
+                            Blended(
+                                &note,
+                                Color::WHITE.with_alpha(opacity(scroll(base.time as f32))),
                             ),
-                            Img(&note),
                             Transform::IDENTITY,
                             3,
                         );
@@ -215,21 +319,29 @@ impl Lane {
                     });
                 }
                 HitObject::LongNote { base, end_time } => {
-                    if (base.time as f32 - position) * (speed / 100.0) > (size.y + 50.0) {
+                    let head_time = if i == lowest_index && is_holding {
+                        (base.time as f32).max(position)
+                    } else {
+                        base.time as f32
+                    };
+                    if scroll(head_time) > (size.y + 50.0) {
                         break;
                     }
+                    let head_alpha = opacity(scroll(head_time));
+                    let tail_alpha = opacity(scroll(*end_time as f32));
                     self.asset_slider_body.execute(|slider_body| {
                         window.draw_ex(
                             &Rectangle::new(
                                 (
                                     pos.x,
-                                    pos.y - hit_line
-                                        + (size.y
-                                            - (*end_time as f32 - position) * (speed / 100.0)),
+                                    pos.y - hit_line + (size.y - scroll(*end_time as f32)),
                                 ),
-                                (size.x, (*end_time - base.time) as f32 * (speed / 100.0)),
+                                (size.x, scroll(*end_time as f32) - scroll(head_time)),
+                            ),
+                            Blended(
+                                &slider_body,
+                                Color::WHITE.with_alpha(head_alpha.min(tail_alpha)),
                             ),
-                            Img(&slider_body),
                             Transform::scale((1, -1)),
                             3,
                         );
@@ -238,15 +350,13 @@ impl Lane {
                     self.asset_slider_end.execute(|slider_end| {
                         window.draw_ex(
                             &Rectangle::new(
+                                (pos.x, pos.y - hit_line + (size.y - scroll(head_time))),
                                 (
-                                    pos.x,
-                                    pos.y - hit_line
-                                        + (size.y
-                                            - (base.time as f32 - position) * (speed / 100.0)),
+                                    size.x,
+                                    size.x * slider_end.area().size.y / slider_end.area().size.x,
                                 ),
-                                (size.x, speed / 4.0),
                             ),
-                            Img(&slider_end),
+                            Blended(&slider_end, Color::WHITE.with_alpha(head_alpha)),
                             Transform::IDENTITY,
                             3,
                         );
@@ -254,13 +364,14 @@ impl Lane {
                             &Rectangle::new(
                                 (
                                     pos.x,
-                                    pos.y - hit_line
-                                        + (size.y
-                                            - (*end_time as f32 - position) * (speed / 100.0)),
+                                    pos.y - hit_line + (size.y - scroll(*end_time as f32)),
+                                ),
+                                (
+                                    size.x,
+                                    size.x * slider_end.area().size.y / slider_end.area().size.x,
                                 ),
-                                (size.x, speed / 4.0),
                             ),
-                            Img(&slider_end),
+                            Blended(&slider_end, Color::WHITE.with_alpha(tail_alpha)),
                             Transform::scale((1, -1)),
                             3,
                         );
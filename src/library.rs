@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{prelude::*, BufReader};
+use std::path::{Path, PathBuf};
+
+use quicksilver::Result;
+
+use crate::bindings;
+
+// osu!mania beatmaps are mode 3 in the [General] section
+const MANIA_MODE: u32 = 3;
+
+#[derive(Clone)]
+pub struct BeatmapInfo {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub creator: String,
+    pub version: String,
+    pub key_count: u32,
+    pub note_count: usize,
+}
+
+pub struct BeatmapSet {
+    pub directory: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub difficulties: Vec<BeatmapInfo>,
+}
+
+fn read_beatmap(path: &Path) -> Option<BeatmapInfo> {
+    let f = BufReader::new(File::open(path).ok()?);
+    let beatmap = osu_format::Parser::new(f.lines()).parse().ok()?;
+    let key_count = beatmap.difficulty.circle_size as u32;
+    if beatmap.general.mode != MANIA_MODE || !bindings::is_supported(key_count) {
+        return None;
+    }
+    Some(BeatmapInfo {
+        path: path.to_path_buf(),
+        title: beatmap.metadata.title,
+        artist: beatmap.metadata.artist,
+        creator: beatmap.metadata.creator,
+        version: beatmap.metadata.version,
+        key_count,
+        note_count: beatmap.hit_objects.len(),
+    })
+}
+
+fn read_set(directory: &Path) -> Result<Option<BeatmapSet>> {
+    let mut difficulties = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "osu")
+        {
+            difficulties.extend(read_beatmap(&path));
+        }
+    }
+    if difficulties.is_empty() {
+        return Ok(None);
+    }
+    difficulties.sort_by_key(|difficulty| (difficulty.key_count, difficulty.note_count));
+    Ok(Some(BeatmapSet {
+        directory: directory.to_path_buf(),
+        title: difficulties[0].title.clone(),
+        artist: difficulties[0].artist.clone(),
+        difficulties,
+    }))
+}
+
+pub fn scan(songs_directory: &Path) -> Result<Vec<BeatmapSet>> {
+    let mut sets = Vec::new();
+    for entry in std::fs::read_dir(songs_directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            // one unreadable set shouldn't hide the rest
+            match read_set(&path) {
+                Ok(set) => sets.extend(set),
+                Err(err) => eprintln!("Skipping {}: {:?}", path.display(), err),
+            }
+        }
+    }
+    sets.sort_by(|a, b| {
+        (a.artist.to_lowercase(), a.title.to_lowercase())
+            .cmp(&(b.artist.to_lowercase(), b.title.to_lowercase()))
+    });
+    Ok(sets)
+}
+
+// osu! identifies beatmaps in replays and scores by the MD5 of the .osu file
+pub fn beatmap_hash(path: &Path) -> Result<String> {
+    Ok(format!("{:x}", md5::compute(std::fs::read(path)?)))
+}
+
+pub fn find_by_hash(songs_directory: &Path, hash: &str) -> Result<Option<PathBuf>> {
+    for set in scan(songs_directory)? {
+        for difficulty in set.difficulties {
+            if beatmap_hash(&difficulty.path)? == hash {
+                return Ok(Some(difficulty.path));
+            }
+        }
+    }
+    Ok(None)
+}
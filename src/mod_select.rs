@@ -0,0 +1,182 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Col, Img},
+        Color, Font, FontStyle,
+    },
+    input::{ButtonState, Key},
+    lifecycle::{Asset, Event, Window},
+    Result,
+};
+
+use crate::bindings::key_name;
+use crate::mods::{Mod, ModIcons, Mods, KEY_MODS};
+use crate::scene::{Scene, Transition};
+
+const SLOT_WIDTH: f32 = 160.0;
+const SLOT_HEIGHT: f32 = 200.0;
+
+// osu! hotkeys, pressing one again cycles through the mods sharing it
+const MOD_GROUPS: [(Key, &[Mod]); 11] = [
+    (Key::Q, &[Mod::Easy]),
+    (Key::W, &[Mod::NoFail]),
+    (Key::E, &[Mod::HalfTime]),
+    (Key::A, &[Mod::HardRock]),
+    (Key::S, &[Mod::SuddenDeath, Mod::Perfect]),
+    (Key::D, &[Mod::DoubleTime, Mod::Nightcore]),
+    (Key::F, &[Mod::Hidden, Mod::FadeIn]),
+    (Key::G, &[Mod::Flashlight]),
+    (Key::C, &[Mod::Random, Mod::Mirror]),
+    (Key::K, &KEY_MODS),
+    (Key::V, &[Mod::Autoplay, Mod::Cinema]),
+];
+
+fn cycle(mods: &mut Mods, group: &[Mod]) {
+    match group.iter().position(|m| mods.contains(*m)) {
+        Some(active) => {
+            mods.toggle(group[active]);
+            if let Some(next) = group.get(active + 1) {
+                mods.toggle(*next);
+            }
+        }
+        None => mods.toggle(group[0]),
+    }
+}
+
+pub struct ModSelect {
+    mods: Rc<Cell<Mods>>,
+    selected: usize,
+    done: bool,
+    font: Asset<Font>,
+    mod_icons: ModIcons,
+}
+
+impl ModSelect {
+    pub fn new(mods: Rc<Cell<Mods>>) -> Result<ModSelect> {
+        Ok(ModSelect {
+            mods,
+            selected: 0,
+            done: false,
+            font: Asset::new(Font::load("static/fonts/DejaVuSans.ttf")),
+            mod_icons: ModIcons::new(),
+        })
+    }
+
+    fn toggle_group(&mut self, group: usize) {
+        let mut mods = self.mods.get();
+        cycle(&mut mods, MOD_GROUPS[group].1);
+        self.mods.set(mods);
+    }
+
+    fn press(&mut self, key: Key) {
+        match key {
+            Key::Escape | Key::F1 => self.done = true,
+            Key::Left => self.selected = self.selected.saturating_sub(1),
+            Key::Right => self.selected = (self.selected + 1).min(MOD_GROUPS.len() - 1),
+            Key::Space | Key::Return => self.toggle_group(self.selected),
+            Key::Back => self.mods.set(Mods::default()),
+            _ => {
+                if let Some(group) = MOD_GROUPS.iter().position(|(hotkey, _)| *hotkey == key) {
+                    self.selected = group;
+                    self.toggle_group(group);
+                }
+            }
+        }
+    }
+}
+
+impl Scene for ModSelect {
+    fn update(&mut self, _window: &mut Window) -> Result<Transition> {
+        if self.done {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn event(&mut self, event: &Event, _window: &mut Window) -> Result<()> {
+        if let Event::Key(key, ButtonState::Pressed) = event {
+            self.press(*key);
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+        window.clear(Color::BLACK)?;
+
+        let mods = self.mods.get();
+        let stage_x = (w - SLOT_WIDTH * MOD_GROUPS.len() as f32) / 2.0;
+        let stage_y = (h - SLOT_HEIGHT) / 2.0;
+        for (group, (_, group_mods)) in MOD_GROUPS.iter().enumerate() {
+            let slot = Rectangle::new(
+                (stage_x + group as f32 * SLOT_WIDTH + 4.0, stage_y),
+                (SLOT_WIDTH - 8.0, SLOT_HEIGHT),
+            );
+            let color = if group == self.selected {
+                Color::from_rgba(80, 160, 255, 0.5)
+            } else {
+                Color::from_rgba(255, 255, 255, 0.1)
+            };
+            window.draw_ex(&slot, Col(color), Transform::IDENTITY, 0);
+
+            // the active mod of the group, or a dimmed first one
+            let shown = group_mods
+                .iter()
+                .find(|m| mods.contains(**m))
+                .map_or((group_mods[0], false), |m| (*m, true));
+            let tint = if shown.1 {
+                Color::WHITE
+            } else {
+                Color::WHITE.with_alpha(0.3)
+            };
+            self.mod_icons
+                .draw_icon(window, shown.0, slot.center(), tint, 1.5)?;
+        }
+
+        self.font.execute(|font| {
+            let title = font.render(
+                "Mods  F1/Escape to close, Backspace to reset",
+                &FontStyle::new(40.0, Color::WHITE),
+            )?;
+            window.draw_ex(
+                &title.area().with_center((w / 2.0, stage_y - 60.0)),
+                Img(&title),
+                Transform::IDENTITY,
+                1,
+            );
+            for (group, (hotkey, _)) in MOD_GROUPS.iter().enumerate() {
+                let name = font.render(&key_name(*hotkey), &FontStyle::new(24.0, Color::WHITE))?;
+                window.draw_ex(
+                    &name.area().with_center((
+                        stage_x + (group as f32 + 0.5) * SLOT_WIDTH,
+                        stage_y + SLOT_HEIGHT - 20.0,
+                    )),
+                    Img(&name),
+                    Transform::IDENTITY,
+                    1,
+                );
+            }
+            let selected = if mods == Mods::default() {
+                "None".to_string()
+            } else {
+                mods.to_string()
+            };
+            let summary = font.render(
+                &format!("Selected: {}", selected),
+                &FontStyle::new(24.0, Color::WHITE),
+            )?;
+            window.draw_ex(
+                &summary
+                    .area()
+                    .with_center((w / 2.0, stage_y + SLOT_HEIGHT + 40.0)),
+                Img(&summary),
+                Transform::IDENTITY,
+                1,
+            );
+            Ok(())
+        })
+    }
+}
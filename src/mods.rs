@@ -0,0 +1,476 @@
+use std::fmt;
+
+use quicksilver::{
+    geom::{Shape, Transform, Vector},
+    graphics::{
+        Background::{Blended, Img},
+        Color, Font, FontStyle, Image,
+    },
+    lifecycle::{Asset, Window},
+    Result,
+};
+
+use crate::hit_score::HitResult;
+use crate::judgement::HitWindows;
+
+// Hidden fades notes out between these heights, 0 is the hit line and 1 the top of the stage
+const HIDDEN_FADE: (f32, f32) = (0.6, 0.4);
+// Fade In fades notes in between these heights
+const FADE_IN_FADE: (f32, f32) = (0.8, 0.6);
+// Flashlight only shows notes this close to the hit line
+const FLASHLIGHT_FADE: (f32, f32) = (0.4, 0.3);
+// same as osu!: HR raises HP drain by 40% up to 10, EZ halves it
+const HARD_ROCK_HP_SCALE: f32 = 1.4;
+const EASY_HP_SCALE: f32 = 0.5;
+// size of the acronym drawn for mods the skin has no icon for
+const ACRONYM_SIZE: f32 = 40.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mod {
+    Easy,
+    NoFail,
+    HardRock,
+    SuddenDeath,
+    Perfect,
+    Hidden,
+    FadeIn,
+    Flashlight,
+    Random,
+    Mirror,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Autoplay,
+    Cinema,
+    DoubleTime,
+    HalfTime,
+    Nightcore,
+}
+
+// in the order they're shown
+pub const ALL_MODS: [Mod; 24] = [
+    Mod::Easy,
+    Mod::NoFail,
+    Mod::HalfTime,
+    Mod::HardRock,
+    Mod::SuddenDeath,
+    Mod::Perfect,
+    Mod::DoubleTime,
+    Mod::Nightcore,
+    Mod::Hidden,
+    Mod::FadeIn,
+    Mod::Flashlight,
+    Mod::Random,
+    Mod::Mirror,
+    Mod::Key1,
+    Mod::Key2,
+    Mod::Key3,
+    Mod::Key4,
+    Mod::Key5,
+    Mod::Key6,
+    Mod::Key7,
+    Mod::Key8,
+    Mod::Key9,
+    Mod::Autoplay,
+    Mod::Cinema,
+];
+
+// KeyN converts the chart to N lanes
+pub const KEY_MODS: [Mod; 9] = [
+    Mod::Key1,
+    Mod::Key2,
+    Mod::Key3,
+    Mod::Key4,
+    Mod::Key5,
+    Mod::Key6,
+    Mod::Key7,
+    Mod::Key8,
+    Mod::Key9,
+];
+
+impl Mod {
+    // same bits as osu!, so they can go straight into .osr replays
+    pub fn bit(self) -> u32 {
+        match self {
+            Mod::NoFail => 1,
+            Mod::Easy => 1 << 1,
+            Mod::Hidden => 1 << 3,
+            Mod::HardRock => 1 << 4,
+            Mod::SuddenDeath => 1 << 5,
+            Mod::DoubleTime => 1 << 6,
+            Mod::HalfTime => 1 << 8,
+            Mod::Nightcore => 1 << 9,
+            Mod::Flashlight => 1 << 10,
+            Mod::Autoplay => 1 << 11,
+            Mod::Perfect => 1 << 14,
+            Mod::Key4 => 1 << 15,
+            Mod::Key5 => 1 << 16,
+            Mod::Key6 => 1 << 17,
+            Mod::Key7 => 1 << 18,
+            Mod::Key8 => 1 << 19,
+            Mod::FadeIn => 1 << 20,
+            Mod::Random => 1 << 21,
+            Mod::Cinema => 1 << 22,
+            Mod::Key9 => 1 << 24,
+            Mod::Key1 => 1 << 26,
+            Mod::Key3 => 1 << 27,
+            Mod::Key2 => 1 << 28,
+            Mod::Mirror => 1 << 30,
+        }
+    }
+
+    pub fn acronym(self) -> &'static str {
+        match self {
+            Mod::Easy => "EZ",
+            Mod::NoFail => "NF",
+            Mod::HardRock => "HR",
+            Mod::SuddenDeath => "SD",
+            Mod::Perfect => "PF",
+            Mod::Hidden => "HD",
+            Mod::FadeIn => "FI",
+            Mod::Flashlight => "FL",
+            Mod::Random => "RD",
+            Mod::Mirror => "MR",
+            Mod::Key1 => "1K",
+            Mod::Key2 => "2K",
+            Mod::Key3 => "3K",
+            Mod::Key4 => "4K",
+            Mod::Key5 => "5K",
+            Mod::Key6 => "6K",
+            Mod::Key7 => "7K",
+            Mod::Key8 => "8K",
+            Mod::Key9 => "9K",
+            Mod::Autoplay => "AT",
+            Mod::Cinema => "CN",
+            Mod::DoubleTime => "DT",
+            Mod::HalfTime => "HT",
+            Mod::Nightcore => "NC",
+        }
+    }
+
+    // the default skin has no Mirror icon
+    pub fn image_path(self) -> Option<&'static str> {
+        Some(match self {
+            Mod::Easy => "static/skin/selection-mod-easy.png",
+            Mod::NoFail => "static/skin/selection-mod-nofail.png",
+            Mod::HardRock => "static/skin/selection-mod-hardrock.png",
+            Mod::SuddenDeath => "static/skin/selection-mod-suddendeath.png",
+            Mod::Perfect => "static/skin/selection-mod-perfect.png",
+            Mod::Hidden => "static/skin/selection-mod-hidden.png",
+            Mod::FadeIn => "static/skin/selection-mod-fadein.png",
+            Mod::Flashlight => "static/skin/selection-mod-flashlight.png",
+            Mod::Autoplay => "static/skin/selection-mod-autoplay.png",
+            Mod::Cinema => "static/skin/selection-mod-cinema.png",
+            Mod::DoubleTime => "static/skin/selection-mod-doubletime.png",
+            Mod::HalfTime => "static/skin/selection-mod-halftime.png",
+            Mod::Nightcore => "static/skin/selection-mod-nightcore.png",
+            Mod::Random => "static/skin/selection-mod-random.png",
+            Mod::Mirror => return None,
+            Mod::Key1 => "static/skin/selection-mod-key1.png",
+            Mod::Key2 => "static/skin/selection-mod-key2.png",
+            Mod::Key3 => "static/skin/selection-mod-key3.png",
+            Mod::Key4 => "static/skin/selection-mod-key4.png",
+            Mod::Key5 => "static/skin/selection-mod-key5.png",
+            Mod::Key6 => "static/skin/selection-mod-key6.png",
+            Mod::Key7 => "static/skin/selection-mod-key7.png",
+            Mod::Key8 => "static/skin/selection-mod-key8.png",
+            Mod::Key9 => "static/skin/selection-mod-key9.png",
+        })
+    }
+
+    fn incompatible(self) -> &'static [Mod] {
+        match self {
+            Mod::Easy => &[Mod::HardRock],
+            Mod::HardRock => &[Mod::Easy],
+            Mod::NoFail => &[Mod::SuddenDeath, Mod::Perfect, Mod::Autoplay, Mod::Cinema],
+            Mod::SuddenDeath => &[Mod::NoFail, Mod::Perfect, Mod::Autoplay, Mod::Cinema],
+            Mod::Perfect => &[Mod::NoFail, Mod::SuddenDeath, Mod::Autoplay, Mod::Cinema],
+            Mod::Hidden => &[Mod::FadeIn],
+            Mod::FadeIn => &[Mod::Hidden],
+            Mod::Autoplay => &[Mod::Cinema, Mod::NoFail, Mod::SuddenDeath, Mod::Perfect],
+            Mod::Cinema => &[Mod::Autoplay, Mod::NoFail, Mod::SuddenDeath, Mod::Perfect],
+            Mod::Flashlight => &[],
+            Mod::DoubleTime => &[Mod::HalfTime, Mod::Nightcore],
+            Mod::HalfTime => &[Mod::DoubleTime, Mod::Nightcore],
+            Mod::Nightcore => &[Mod::DoubleTime, Mod::HalfTime],
+            Mod::Random => &[Mod::Mirror],
+            Mod::Mirror => &[Mod::Random],
+            Mod::Key1
+            | Mod::Key2
+            | Mod::Key3
+            | Mod::Key4
+            | Mod::Key5
+            | Mod::Key6
+            | Mod::Key7
+            | Mod::Key8
+            | Mod::Key9 => &KEY_MODS,
+        }
+    }
+}
+
+// mods that osu! always writes together with another one
+const IMPLIED_MODS: [(Mod, Mod); 2] = [
+    (Mod::Nightcore, Mod::DoubleTime),
+    (Mod::Perfect, Mod::SuddenDeath),
+];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Mods(u32);
+
+impl fmt::Display for Mods {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for m in self.iter() {
+            write!(f, "{}", m.acronym())?;
+        }
+        Ok(())
+    }
+}
+
+fn fade(height: f32, (from, to): (f32, f32)) -> f32 {
+    ((height - to) / (from - to)).clamp(0.0, 1.0)
+}
+
+impl Mods {
+    pub fn from_bits(bits: u32) -> Mods {
+        let mut mods = Mods(ALL_MODS.iter().fold(0, |acc, m| acc | (bits & m.bit())));
+        for (m, implied) in IMPLIED_MODS.iter() {
+            if mods.contains(*m) {
+                mods.0 &= !implied.bit();
+            }
+        }
+        mods
+    }
+
+    // osu! sets the DT bit along with NC and the SD bit along with PF
+    pub fn bits(self) -> u32 {
+        IMPLIED_MODS
+            .iter()
+            .filter(|(m, _)| self.contains(*m))
+            .fold(self.0, |acc, (_, implied)| acc | implied.bit())
+    }
+
+    // acronyms in any order, like "HDFL"
+    pub fn parse(text: &str) -> Mods {
+        let text: Vec<char> = text.trim().to_uppercase().chars().collect();
+        let mut mods = Mods::default();
+        for acronym in text.chunks(2) {
+            let acronym: String = acronym.iter().collect();
+            if let Some(m) = ALL_MODS.iter().find(|m| m.acronym() == acronym) {
+                if !mods.contains(*m) {
+                    mods.toggle(*m);
+                }
+            }
+        }
+        mods
+    }
+
+    // --mods=HDFL, --autoplay is kept as a shorthand for AT
+    pub fn from_args() -> Mods {
+        let mut mods = std::env::args()
+            .find(|arg| arg.starts_with("--mods="))
+            .map_or(Mods::default(), |arg| Mods::parse(&arg["--mods=".len()..]));
+        if std::env::args().any(|arg| arg == "--autoplay") && !mods.contains(Mod::Autoplay) {
+            mods.toggle(Mod::Autoplay);
+        }
+        mods
+    }
+
+    pub fn contains(self, m: Mod) -> bool {
+        self.0 & m.bit() != 0
+    }
+
+    pub fn toggle(&mut self, m: Mod) {
+        if self.contains(m) {
+            self.0 &= !m.bit();
+        } else {
+            for other in m.incompatible() {
+                self.0 &= !other.bit();
+            }
+            self.0 |= m.bit();
+        }
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Mod> {
+        ALL_MODS.iter().cloned().filter(move |m| self.contains(*m))
+    }
+
+    // Cinema plays itself too
+    pub fn is_autoplay(self) -> bool {
+        self.contains(Mod::Autoplay) || self.contains(Mod::Cinema)
+    }
+
+    // song speed
+    pub fn rate(self) -> f32 {
+        if self.contains(Mod::DoubleTime) || self.contains(Mod::Nightcore) {
+            1.5
+        } else if self.contains(Mod::HalfTime) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+
+    // osu!mania multipliers
+    pub fn score_multiplier(self) -> f32 {
+        self.iter()
+            .map(|m| match m {
+                Mod::Easy | Mod::NoFail | Mod::HalfTime => 0.5,
+                _ => 1.0,
+            })
+            .product()
+    }
+
+    pub fn hit_windows(self, od: f32) -> HitWindows {
+        let windows = HitWindows::new(od);
+        let windows = if self.contains(Mod::HardRock) {
+            windows.hard_rock()
+        } else if self.contains(Mod::Easy) {
+            windows.easy()
+        } else {
+            windows
+        };
+        windows.rate(self.rate())
+    }
+
+    pub fn hp_drain_rate(self, hp_drain_rate: f32) -> f32 {
+        if self.contains(Mod::HardRock) {
+            (hp_drain_rate * HARD_ROCK_HP_SCALE).min(10.0)
+        } else if self.contains(Mod::Easy) {
+            hp_drain_rate * EASY_HP_SCALE
+        } else {
+            hp_drain_rate
+        }
+    }
+
+    // lane count the chart is converted to
+    pub fn key_count(self) -> Option<usize> {
+        KEY_MODS
+            .iter()
+            .position(|m| self.contains(*m))
+            .map(|index| index + 1)
+    }
+
+    pub fn can_fail(self) -> bool {
+        !self.contains(Mod::NoFail)
+    }
+
+    // SD fails on the first miss, PF on anything short of a 300g
+    pub fn fails_on(self, result: HitResult) -> bool {
+        match result {
+            HitResult::NoHit => false,
+            HitResult::Miss => self.contains(Mod::SuddenDeath) || self.contains(Mod::Perfect),
+            HitResult::Hit300g => false,
+            _ => self.contains(Mod::Perfect),
+        }
+    }
+
+    // opacity of a note `height` above the hit line, 1 being the top of the stage
+    pub fn note_opacity(self, height: f32) -> f32 {
+        let mut opacity: f32 = 1.0;
+        if self.contains(Mod::Hidden) {
+            opacity = opacity.min(fade(height, HIDDEN_FADE));
+        }
+        if self.contains(Mod::FadeIn) {
+            opacity = opacity.min(1.0 - fade(height, FADE_IN_FADE));
+        }
+        if self.contains(Mod::Flashlight) {
+            opacity = opacity.min(1.0 - fade(height, FLASHLIGHT_FADE));
+        }
+        opacity
+    }
+}
+
+enum Icon {
+    Skin(Asset<Image>),
+    // rendered from the acronym the first time it's drawn
+    Acronym(Option<Image>),
+}
+
+// The selection-mod-* icons of every mod, drawn right to left from a corner
+pub struct ModIcons {
+    icons: Vec<Icon>,
+    font: Asset<Font>,
+}
+
+impl Default for ModIcons {
+    fn default() -> ModIcons {
+        ModIcons::new()
+    }
+}
+
+impl ModIcons {
+    pub fn new() -> ModIcons {
+        ModIcons {
+            icons: ALL_MODS
+                .iter()
+                .map(|m| match m.image_path() {
+                    Some(path) => Icon::Skin(Asset::new(Image::load(path))),
+                    None => Icon::Acronym(None),
+                })
+                .collect(),
+            font: Asset::new(Font::load("static/fonts/DejaVuSans.ttf")),
+        }
+    }
+
+    // the icon of `m`, or its acronym if there is none, once it's loaded
+    fn image(&mut self, m: Mod) -> Result<Option<Image>> {
+        let index = ALL_MODS.iter().position(|other| *other == m).unwrap();
+        let mut image = None;
+        match &mut self.icons[index] {
+            Icon::Skin(icon) => icon.execute(|icon| {
+                image = Some(icon.clone());
+                Ok(())
+            })?,
+            Icon::Acronym(Some(acronym)) => image = Some(acronym.clone()),
+            Icon::Acronym(cached) => self.font.execute(|font| {
+                let acronym =
+                    font.render(m.acronym(), &FontStyle::new(ACRONYM_SIZE, Color::WHITE))?;
+                *cached = Some(acronym.clone());
+                image = Some(acronym);
+                Ok(())
+            })?,
+        }
+        Ok(image)
+    }
+
+    pub fn draw_icon(
+        &mut self,
+        window: &mut Window,
+        m: Mod,
+        center: Vector,
+        tint: Color,
+        scale: f32,
+    ) -> Result<()> {
+        if let Some(image) = self.image(m)? {
+            window.draw_ex(
+                &image.area().with_center(center),
+                Blended(&image, tint),
+                Transform::scale((scale, scale)),
+                6,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn draw(&mut self, window: &mut Window, mods: Mods, top_right: Vector) -> Result<()> {
+        let mut x = top_right.x;
+        for m in mods.iter() {
+            if let Some(image) = self.image(m)? {
+                x -= image.area().size.x;
+                window.draw_ex(
+                    &image.area().translate((x, top_right.y)),
+                    Img(&image),
+                    Transform::IDENTITY,
+                    6,
+                );
+            }
+        }
+        Ok(())
+    }
+}
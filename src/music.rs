@@ -0,0 +1,168 @@
+use rodio::{Decoder, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use quicksilver::Result;
+
+// samples decoded between handing them over to the players
+const DECODE_CHUNK: usize = 16384;
+// samples a player copies out of the shared buffer at a time
+const READ_CHUNK: usize = 4096;
+// a player that's this far ahead of the decoder gives up instead of stalling the audio
+const MAX_DECODE_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Buffer {
+    samples: Vec<i16>,
+    done: bool,
+}
+
+// A song decoded once on a worker thread, any number of `MusicSource`s can play it
+// from anywhere without decoding it again
+pub struct Music {
+    buffer: Mutex<Buffer>,
+    decoded: Condvar,
+    channels: u16,
+    sample_rate: u32,
+}
+
+// Marks the music as fully decoded however the worker stops, a decoder panicking on a
+// corrupt frame included, so no player waits for samples that never come
+struct Finish(Arc<Music>);
+
+impl Drop for Finish {
+    fn drop(&mut self) {
+        self.0.lock_buffer().done = true;
+        self.0.decoded.notify_all();
+    }
+}
+
+fn open_music(path: &str) -> Result<Decoder<BufReader<File>>> {
+    let file = File::open(path)?;
+    Decoder::new(BufReader::new(file)).map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Failed to decode {}: {:?}", path, err),
+        )
+        .into()
+    })
+}
+
+impl Music {
+    pub fn decode(path: &str) -> Result<Arc<Music>> {
+        let mut decoder = open_music(path)?;
+        let music = Arc::new(Music {
+            buffer: Mutex::new(Buffer::default()),
+            decoded: Condvar::new(),
+            channels: decoder.channels(),
+            sample_rate: decoder.sample_rate(),
+        });
+        let finish = Finish(music.clone());
+        thread::spawn(move || loop {
+            let shared = &finish.0;
+            let chunk: Vec<i16> = decoder.by_ref().take(DECODE_CHUNK).collect();
+            // nobody is going to play it any more
+            if Arc::strong_count(shared) == 1 {
+                return;
+            }
+            let done = chunk.len() < DECODE_CHUNK;
+            shared.lock_buffer().samples.extend(chunk);
+            shared.decoded.notify_all();
+            if done {
+                return;
+            }
+        });
+        Ok(music)
+    }
+
+    // a panic elsewhere can't leave the samples half written, so a poisoned lock is still fine
+    fn lock_buffer(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+pub struct MusicSource {
+    music: Arc<Music>,
+    // index in the song of the next sample after `chunk`
+    position: usize,
+    chunk: Vec<i16>,
+    chunk_index: usize,
+}
+
+impl MusicSource {
+    // `start` is a sample index, it's rounded down to a whole frame
+    pub fn new(music: Arc<Music>, start: usize) -> MusicSource {
+        let channels = music.channels as usize;
+        MusicSource {
+            music,
+            position: start / channels * channels,
+            chunk: Vec::new(),
+            chunk_index: 0,
+        }
+    }
+}
+
+impl Iterator for MusicSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.chunk_index >= self.chunk.len() {
+            let mut buffer = self.music.lock_buffer();
+            // only waits when playing ahead of the decoder, right after a long seek
+            let deadline = Instant::now() + MAX_DECODE_WAIT;
+            while buffer.samples.len() <= self.position && !buffer.done {
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                buffer = self
+                    .music
+                    .decoded
+                    .wait_timeout(buffer, deadline - now)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+            }
+            let end = buffer.samples.len().min(self.position + READ_CHUNK);
+            if self.position >= end {
+                return None;
+            }
+            self.chunk.clear();
+            self.chunk
+                .extend_from_slice(&buffer.samples[self.position..end]);
+            self.chunk_index = 0;
+            self.position = end;
+        }
+        let sample = self.chunk[self.chunk_index];
+        self.chunk_index += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MusicSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.music.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.music.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,88 @@
+use osu_format::TimingPoint;
+use quicksilver::{lifecycle::Asset, sound::Sound, Result};
+
+const KICK: usize = 0;
+const CLAP: usize = 1;
+const FINISH: usize = 2;
+const SOUND_PATHS: [&str; 3] = [
+    "static/skin/drum-hitnormal.wav",
+    "static/skin/drum-hitclap.wav",
+    "static/skin/drum-hitfinish.wav",
+];
+const VOLUME: f32 = 0.6;
+// beats further behind than this are skipped instead of played late
+const MAX_LATENESS: f32 = 50.0;
+// a cymbal marks the start of every few measures
+const MEASURES_PER_FINISH: u32 = 4;
+
+// The Nightcore beat track: kicks on the strong beats, claps on the weak ones
+pub struct NightcoreBeat {
+    beats: Vec<(f32, usize)>,
+    next: usize,
+    sounds: Vec<Asset<Sound>>,
+}
+
+fn beats(timing_points: &[TimingPoint], end_position: f32) -> Vec<(f32, usize)> {
+    let mut red_lines: Vec<&TimingPoint> = timing_points
+        .iter()
+        .filter(|timing_point| timing_point.milliseconds_per_beat > 0.0)
+        .collect();
+    red_lines.sort_by_key(|timing_point| timing_point.offset);
+
+    let mut beats = Vec::new();
+    for (i, red_line) in red_lines.iter().enumerate() {
+        let end = red_lines
+            .get(i + 1)
+            .map_or(end_position, |next| next.offset as f32);
+        let meter = red_line.meter.max(1);
+        let mut beat = 0;
+        loop {
+            let time = red_line.offset as f32 + beat as f32 * red_line.milliseconds_per_beat;
+            if time >= end {
+                break;
+            }
+            let beat_in_measure = beat % meter;
+            if beat_in_measure == 0 && (beat / meter) % MEASURES_PER_FINISH == 0 {
+                beats.push((time, FINISH));
+            }
+            let sound = if beat_in_measure % 2 == 0 { KICK } else { CLAP };
+            beats.push((time, sound));
+            beat += 1;
+        }
+    }
+    beats
+}
+
+impl NightcoreBeat {
+    pub fn new(timing_points: &[TimingPoint], end_position: f32) -> NightcoreBeat {
+        NightcoreBeat {
+            beats: beats(timing_points, end_position),
+            next: 0,
+            sounds: SOUND_PATHS
+                .iter()
+                .map(|path| Asset::new(Sound::load(*path)))
+                .collect(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+
+    pub fn update(&mut self, position: f32) -> Result<()> {
+        while let Some((time, sound)) = self.beats.get(self.next).cloned() {
+            if time > position {
+                break;
+            }
+            self.next += 1;
+            if position - time > MAX_LATENESS {
+                continue;
+            }
+            self.sounds[sound].execute(|sound| {
+                sound.set_volume(VOLUME);
+                sound.play()
+            })?;
+        }
+        Ok(())
+    }
+}
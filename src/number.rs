@@ -7,37 +7,52 @@ use quicksilver::{
 
 pub struct Number {
     digits: Vec<Asset<Image>>,
+    symbols: Vec<(char, Asset<Image>)>,
 }
 
 impl Number {
-    pub fn new() -> Result<Number> {
+    pub fn new(prefix: &str) -> Result<Number> {
         let mut digits = Vec::new();
         for i in 0..10 {
             digits.push(Asset::new(Image::load(format!(
-                "static/skin/score-{}.png",
-                i
+                "static/skin/{}-{}.png",
+                prefix, i
             ))));
         }
-        Ok(Number { digits })
+        Ok(Number {
+            digits,
+            symbols: Vec::new(),
+        })
     }
 
-    pub fn draw(&mut self, window: &mut Window, pos: &Vector, size: &Vector, number: u32) {
-        let digits: Vec<_> = number
-            .to_string()
-            .chars()
-            .map(|d| d.to_digit(10).unwrap())
-            .collect();
-        for (i, digit) in digits.iter().enumerate() {
-            self.digits[*digit as usize].execute(|image| {
+    pub fn with_symbol(mut self, symbol: char, path: &str) -> Number {
+        self.symbols
+            .push((symbol, Asset::new(Image::load(path.to_string()))));
+        self
+    }
+
+    pub fn draw_str(&mut self, window: &mut Window, pos: &Vector, text: &str) {
+        let mut x = pos.x;
+        for c in text.chars() {
+            let asset = match c.to_digit(10) {
+                Some(digit) => &mut self.digits[digit as usize],
+                None => match self.symbols.iter_mut().find(|(symbol, _)| *symbol == c) {
+                    Some((_, asset)) => asset,
+                    None => continue,
+                },
+            };
+            asset.execute(|image| {
                 window.draw(
-                    &Rectangle::new(
-                        (pos.x + i as f32 * image.area().size.x, pos.y),
-                        (image.area().size.x, image.area().size.y),
-                    ),
+                    &Rectangle::new((x, pos.y), (image.area().size.x, image.area().size.y)),
                     Img(&image),
                 );
+                x += image.area().size.x;
                 Ok(())
             });
         }
     }
+
+    pub fn draw(&mut self, window: &mut Window, pos: &Vector, size: &Vector, number: u32) {
+        self.draw_str(window, pos, &number.to_string());
+    }
 }
@@ -0,0 +1,551 @@
+use std::fs::{self, File};
+use std::io::{prelude::*, BufReader, BufWriter};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lzma_rs::compress::{Options, UnpackedSize};
+use quicksilver::Result;
+
+use crate::hit_score::HitResult;
+use crate::mods::{Mod, Mods};
+use crate::replay::{Replay, ReplayEvent};
+use crate::score::Score;
+
+const MANIA_MODE: u8 = 3;
+const OSU_VERSION: u32 = 20190906;
+// last frame of a replay holds the RNG seed in place of the key state
+const SEED_FRAME_DELTA: i32 = -12345;
+// stable starts every replay with a couple of frames at this y that aren't input
+const DUMMY_FRAME_Y: f32 = -500.0;
+// .NET ticks (100ns since 0001-01-01) at the unix epoch
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+const TICKS_PER_SECOND: i64 = 10_000_000;
+
+// w|x|y|z in the replay data, for mania x is the bitmask of held columns
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub time_delta: i32,
+    pub keys: u32,
+}
+
+pub struct OsrReplay {
+    pub mode: u8,
+    pub version: u32,
+    pub beatmap_hash: String,
+    pub player_name: String,
+    pub replay_hash: String,
+    pub count_300: u16,
+    pub count_100: u16,
+    pub count_50: u16,
+    pub count_geki: u16,
+    pub count_katu: u16,
+    pub count_miss: u16,
+    pub score: u32,
+    pub max_combo: u16,
+    pub perfect: bool,
+    pub mods: u32,
+    // (time in ms, hp from 0 to 1)
+    pub life_bar: Vec<(i32, f32)>,
+    pub timestamp: i64,
+    pub frames: Vec<Frame>,
+    pub seed: u32,
+    pub score_id: i64,
+}
+
+fn invalid_data(reason: &str) -> quicksilver::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Invalid osr replay: {}", reason),
+    )
+    .into()
+}
+
+// osu! lays out Random's columns with its own RNG, so they'd come out different on the other side
+fn check_random(mods: u32) -> Result<()> {
+    if Mods::from_bits(mods).contains(Mod::Random) {
+        return Err(invalid_data(
+            "Random (RD) column order differs from osu!'s, these replays can't be shared",
+        ));
+    }
+    Ok(())
+}
+
+fn read_bytes<R: Read>(reader: &mut R, count: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; count];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    Ok(read_bytes(reader, 1)?[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> Result<i64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(i64::from_le_bytes(bytes))
+}
+
+fn read_uleb128<R: Read>(reader: &mut R) -> Result<usize> {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = read_u8(reader)?;
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+// 0x00 for an empty string, otherwise 0x0b, ULEB128 length and UTF-8 bytes
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    match read_u8(reader)? {
+        0x00 => Ok(String::new()),
+        0x0b => {
+            let length = read_uleb128(reader)?;
+            String::from_utf8(read_bytes(reader, length)?)
+                .map_err(|_| invalid_data("string is not UTF-8"))
+        }
+        _ => Err(invalid_data("bad string marker")),
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> Result<()> {
+    if string.is_empty() {
+        writer.write_all(&[0x00])?;
+        return Ok(());
+    }
+    writer.write_all(&[0x0b])?;
+    let mut length = string.len();
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            writer.write_all(&[byte])?;
+            break;
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+    writer.write_all(string.as_bytes())?;
+    Ok(())
+}
+
+fn parse_life_bar(text: &str) -> Vec<(i32, f32)> {
+    text.split(',')
+        .filter_map(|point| {
+            let mut parts = point.split('|');
+            let time = parts.next()?.trim().parse().ok()?;
+            let hp = parts.next()?.trim().parse().ok()?;
+            Some((time, hp))
+        })
+        .collect()
+}
+
+// (w, x, y) of a frame
+fn parse_frame(frame: &str) -> Option<(i32, u32, f32)> {
+    let mut parts = frame.split('|').map(str::trim);
+    let time_delta = parts.next()?.parse().ok()?;
+    let keys = parts.next()?.parse::<f32>().ok()? as u32;
+    let y = parts.next()?.parse().ok()?;
+    Some((time_delta, keys, y))
+}
+
+fn parse_frames(text: &str) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    // the time of dropped frames still counts towards the next one
+    let mut skipped = 0;
+    for (time_delta, keys, y) in text.split(',').filter_map(parse_frame) {
+        if time_delta == SEED_FRAME_DELTA {
+            continue;
+        }
+        if y == DUMMY_FRAME_Y {
+            skipped += time_delta;
+            continue;
+        }
+        frames.push(Frame {
+            time_delta: time_delta + skipped,
+            keys,
+        });
+        skipped = 0;
+    }
+    frames
+}
+
+fn parse_seed(text: &str) -> u32 {
+    text.split(',')
+        .filter_map(|frame| {
+            let parts: Vec<&str> = frame.split('|').collect();
+            if parts.len() < 4 || parts[0].trim().parse() != Ok(SEED_FRAME_DELTA) {
+                return None;
+            }
+            parts[3].trim().parse().ok()
+        })
+        .next()
+        .unwrap_or(0)
+}
+
+fn decompress(data: &[u8]) -> Result<String> {
+    let mut output = Vec::new();
+    lzma_rs::lzma_decompress(&mut BufReader::new(data), &mut output)
+        .map_err(|err| invalid_data(&format!("{:?}", err)))?;
+    String::from_utf8(output).map_err(|_| invalid_data("frames are not UTF-8"))
+}
+
+fn compress(text: &str) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    lzma_rs::lzma_compress_with_options(
+        &mut BufReader::new(text.as_bytes()),
+        &mut output,
+        &Options {
+            unpacked_size: UnpackedSize::WriteToHeader(Some(text.len() as u64)),
+        },
+    )?;
+    Ok(output)
+}
+
+// frames hold whole milliseconds, recorded inputs are already judged at them
+pub fn frames_from_events(events: &[ReplayEvent]) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    let mut keys = 0;
+    let mut last_time = 0;
+    for event in events {
+        if event.column >= 32 {
+            continue;
+        }
+        if event.pressed {
+            keys |= 1 << event.column;
+        } else {
+            keys &= !(1 << event.column);
+        }
+        let time = event.position.round() as i32;
+        frames.push(Frame {
+            time_delta: time - last_time,
+            keys,
+        });
+        last_time = time;
+    }
+    frames
+}
+
+pub fn events_from_frames(frames: &[Frame]) -> Vec<ReplayEvent> {
+    let mut events = Vec::new();
+    let mut keys = 0;
+    let mut time = 0;
+    for frame in frames {
+        time += frame.time_delta;
+        let changed = keys ^ frame.keys;
+        for column in (0..32).filter(|column| changed & (1 << column) != 0) {
+            events.push(ReplayEvent {
+                position: time as f32,
+                column,
+                pressed: frame.keys & (1 << column) != 0,
+            });
+        }
+        keys = frame.keys;
+    }
+    events
+}
+
+fn player_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "Player".to_string())
+}
+
+impl OsrReplay {
+    pub fn new(
+        replay: &Replay,
+        score: &Score,
+        life_bar: &[(f32, f32)],
+        beatmap_hash: &str,
+    ) -> OsrReplay {
+        let count = |result| score.count(result) as u16;
+        let player_name = player_name();
+        let unix_seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+        let replay_hash = format!(
+            "{:x}",
+            md5::compute(format!(
+                "{}osu{}{}{}{:?}",
+                score.max_combo(),
+                player_name,
+                beatmap_hash,
+                score.score(),
+                score.grade(replay.mods)
+            ))
+        );
+        OsrReplay {
+            mode: MANIA_MODE,
+            version: OSU_VERSION,
+            beatmap_hash: beatmap_hash.to_string(),
+            player_name,
+            replay_hash,
+            count_300: count(HitResult::Hit300),
+            count_100: count(HitResult::Hit100),
+            count_50: count(HitResult::Hit50),
+            count_geki: count(HitResult::Hit300g),
+            count_katu: count(HitResult::Hit200),
+            count_miss: count(HitResult::Miss),
+            score: score.score(),
+            max_combo: score.max_combo() as u16,
+            perfect: score.count(HitResult::Miss) == 0,
+            mods: replay.mods.bits(),
+            life_bar: life_bar
+                .iter()
+                .map(|(position, hp)| (position.round() as i32, *hp))
+                .collect(),
+            timestamp: UNIX_EPOCH_TICKS + unix_seconds * TICKS_PER_SECOND,
+            frames: frames_from_events(&replay.events),
+            seed: replay.seed,
+            score_id: 0,
+        }
+    }
+
+    pub fn to_replay(&self, map_path: &str) -> Replay {
+        Replay {
+            map_path: map_path.to_string(),
+            mods: Mods::from_bits(self.mods),
+            seed: self.seed,
+            events: events_from_frames(&self.frames),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<OsrReplay> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let reader = &mut reader;
+        let mode = read_u8(reader)?;
+        if mode != MANIA_MODE {
+            return Err(invalid_data("not an osu!mania replay"));
+        }
+        let version = read_u32(reader)?;
+        let beatmap_hash = read_string(reader)?;
+        let player_name = read_string(reader)?;
+        let replay_hash = read_string(reader)?;
+        let count_300 = read_u16(reader)?;
+        let count_100 = read_u16(reader)?;
+        let count_50 = read_u16(reader)?;
+        let count_geki = read_u16(reader)?;
+        let count_katu = read_u16(reader)?;
+        let count_miss = read_u16(reader)?;
+        let score = read_u32(reader)?;
+        let max_combo = read_u16(reader)?;
+        let perfect = read_u8(reader)? != 0;
+        let mods = read_u32(reader)?;
+        check_random(mods)?;
+        let life_bar = parse_life_bar(&read_string(reader)?);
+        let timestamp = read_i64(reader)?;
+        let data_length = read_u32(reader)? as usize;
+        let frame_data = decompress(&read_bytes(reader, data_length)?)?;
+        let frames = parse_frames(&frame_data);
+        let seed = parse_seed(&frame_data);
+        // older replays end right after the frames
+        let score_id = read_i64(reader).unwrap_or(0);
+        Ok(OsrReplay {
+            mode,
+            version,
+            beatmap_hash,
+            player_name,
+            replay_hash,
+            count_300,
+            count_100,
+            count_50,
+            count_geki,
+            count_katu,
+            count_miss,
+            score,
+            max_combo,
+            perfect,
+            mods,
+            life_bar,
+            timestamp,
+            frames,
+            seed,
+            score_id,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        check_random(self.mods)?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let life_bar: String = self
+            .life_bar
+            .iter()
+            .map(|(time, hp)| format!("{}|{},", time, hp))
+            .collect();
+        let mut frames: String = self
+            .frames
+            .iter()
+            .map(|frame| format!("{}|{}|0|0,", frame.time_delta, frame.keys))
+            .collect();
+        frames.push_str(&format!("{}|0|0|{},", SEED_FRAME_DELTA, self.seed));
+        let data = compress(&frames)?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        let writer = &mut writer;
+        writer.write_all(&[self.mode])?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        write_string(writer, &self.beatmap_hash)?;
+        write_string(writer, &self.player_name)?;
+        write_string(writer, &self.replay_hash)?;
+        for count in &[
+            self.count_300,
+            self.count_100,
+            self.count_50,
+            self.count_geki,
+            self.count_katu,
+            self.count_miss,
+        ] {
+            writer.write_all(&count.to_le_bytes())?;
+        }
+        writer.write_all(&self.score.to_le_bytes())?;
+        writer.write_all(&self.max_combo.to_le_bytes())?;
+        writer.write_all(&[self.perfect as u8])?;
+        writer.write_all(&self.mods.to_le_bytes())?;
+        write_string(writer, &life_bar)?;
+        writer.write_all(&self.timestamp.to_le_bytes())?;
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(&data)?;
+        writer.write_all(&self.score_id.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(position: f32, column: usize, pressed: bool) -> ReplayEvent {
+        ReplayEvent {
+            position,
+            column,
+            pressed,
+        }
+    }
+
+    #[test]
+    fn events_survive_frames() {
+        let events = vec![
+            event(1000.0, 0, true),
+            event(1000.0, 2, true),
+            event(1040.0, 0, false),
+            event(1500.0, 1, true),
+            event(1500.0, 2, false),
+            event(2250.0, 1, false),
+        ];
+        assert_eq!(events_from_frames(&frames_from_events(&events)), events);
+    }
+
+    #[test]
+    fn dummy_frames_are_dropped() {
+        let frames = parse_frames("0|256|-500|0,-1|256|-500|0,1001|1|0|0,40|0|0|0,-12345|0|0|7,");
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    time_delta: 1000,
+                    keys: 1
+                },
+                Frame {
+                    time_delta: 40,
+                    keys: 0
+                },
+            ]
+        );
+    }
+
+    fn replay(mods: u32) -> OsrReplay {
+        OsrReplay {
+            mode: MANIA_MODE,
+            version: OSU_VERSION,
+            beatmap_hash: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+            player_name: "Player".to_string(),
+            replay_hash: String::new(),
+            count_300: 120,
+            count_100: 8,
+            count_50: 1,
+            count_geki: 300,
+            count_katu: 12,
+            count_miss: 2,
+            score: 912_345,
+            max_combo: 321,
+            perfect: false,
+            mods,
+            life_bar: vec![(0, 1.0), (2000, 0.75)],
+            timestamp: UNIX_EPOCH_TICKS,
+            frames: vec![
+                Frame {
+                    time_delta: 1000,
+                    keys: 5,
+                },
+                Frame {
+                    time_delta: 40,
+                    keys: 4,
+                },
+                Frame {
+                    time_delta: 0,
+                    keys: 0,
+                },
+            ],
+            seed: 42,
+            score_id: 7,
+        }
+    }
+
+    #[test]
+    fn random_is_not_exported() {
+        let path = std::env::temp_dir().join(format!("osr_random_{}.osr", std::process::id()));
+        let mut mods = Mods::default();
+        mods.toggle(Mod::Random);
+        assert!(replay(mods.bits()).save(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn osr_round_trip() {
+        let replay = replay(1 << 3);
+        let path = std::env::temp_dir().join(format!("osr_round_trip_{}.osr", std::process::id()));
+        replay.save(&path).unwrap();
+        let loaded = OsrReplay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.mode, replay.mode);
+        assert_eq!(loaded.version, replay.version);
+        assert_eq!(loaded.beatmap_hash, replay.beatmap_hash);
+        assert_eq!(loaded.player_name, replay.player_name);
+        assert_eq!(loaded.replay_hash, replay.replay_hash);
+        assert_eq!(
+            (loaded.count_300, loaded.count_100, loaded.count_50),
+            (replay.count_300, replay.count_100, replay.count_50)
+        );
+        assert_eq!(
+            (loaded.count_geki, loaded.count_katu, loaded.count_miss),
+            (replay.count_geki, replay.count_katu, replay.count_miss)
+        );
+        assert_eq!(loaded.score, replay.score);
+        assert_eq!(loaded.max_combo, replay.max_combo);
+        assert_eq!(loaded.perfect, replay.perfect);
+        assert_eq!(loaded.mods, replay.mods);
+        assert_eq!(loaded.life_bar, replay.life_bar);
+        assert_eq!(loaded.timestamp, replay.timestamp);
+        assert_eq!(loaded.frames, replay.frames);
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.score_id, replay.score_id);
+    }
+}
@@ -0,0 +1,111 @@
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Blended, Img},
+        Color, Image,
+    },
+    input::Key,
+    lifecycle::{Asset, Window},
+    Result,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PauseChoice {
+    Continue,
+    Retry,
+    Back,
+}
+
+const PAUSE_CHOICES: [PauseChoice; 3] =
+    [PauseChoice::Continue, PauseChoice::Retry, PauseChoice::Back];
+// there's nothing to continue after failing
+const FAIL_CHOICES: [PauseChoice; 2] = [PauseChoice::Retry, PauseChoice::Back];
+
+impl PauseChoice {
+    fn image_path(self) -> &'static str {
+        match self {
+            PauseChoice::Continue => "static/skin/pause-continue.png",
+            PauseChoice::Retry => "static/skin/pause-retry.png",
+            PauseChoice::Back => "static/skin/pause-back.png",
+        }
+    }
+}
+
+pub struct PauseMenu {
+    choices: &'static [PauseChoice],
+    selected: usize,
+    asset_overlay: Asset<Image>,
+    asset_buttons: Vec<Asset<Image>>,
+}
+
+impl PauseMenu {
+    pub fn new() -> Result<PauseMenu> {
+        Ok(PauseMenu {
+            choices: &PAUSE_CHOICES,
+            selected: 0,
+            asset_overlay: Asset::new(Image::load("static/skin/pause-overlay.png")),
+            asset_buttons: PAUSE_CHOICES
+                .iter()
+                .map(|choice| Asset::new(Image::load(choice.image_path())))
+                .collect(),
+        })
+    }
+
+    pub fn open(&mut self, failed: bool) {
+        self.choices = if failed {
+            &FAIL_CHOICES
+        } else {
+            &PAUSE_CHOICES
+        };
+        self.selected = 0;
+    }
+
+    // Up/Down pick a button, Return chooses it and Escape continues if it can
+    pub fn press(&mut self, key: Key) -> Option<PauseChoice> {
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(self.choices.len() - 1),
+            Key::Return | Key::Space => return Some(self.choices[self.selected]),
+            Key::Escape if self.choices.contains(&PauseChoice::Continue) => {
+                return Some(PauseChoice::Continue)
+            }
+            _ => (),
+        }
+        None
+    }
+
+    // the overlay is left out after failing, the fail screen is already behind the buttons
+    pub fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+        if self.choices.contains(&PauseChoice::Continue) {
+            self.asset_overlay.execute(|image| {
+                window.draw_ex(
+                    &Rectangle::new((0, 0), (w, h)),
+                    Img(&image),
+                    Transform::IDENTITY,
+                    12,
+                );
+                Ok(())
+            })?;
+        }
+        for (i, choice) in self.choices.iter().enumerate() {
+            let index = PAUSE_CHOICES.iter().position(|c| c == choice).unwrap();
+            let (tint, scale) = if i == self.selected {
+                (Color::WHITE, 1.1)
+            } else {
+                (Color::WHITE.with_alpha(0.6), 1.0)
+            };
+            let center = Vector::new(w / 2.0, h * (0.3 + 0.2 * i as f32));
+            self.asset_buttons[index].execute(|image| {
+                window.draw_ex(
+                    &image.area().with_center(center),
+                    Blended(&image, tint),
+                    Transform::scale((scale, scale)),
+                    13,
+                );
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
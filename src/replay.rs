@@ -0,0 +1,182 @@
+use std::fs::{self, File};
+use std::io::{prelude::*, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quicksilver::{input::Key, Result};
+
+use crate::input::KeyEvent;
+use crate::mods::Mods;
+
+pub const REPLAY_DIRECTORY: &str = "replays";
+const HEADER: &str = "rust-ddr replay v1";
+
+// Keys are stored as columns so a replay still plays back after rebinding
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub position: f32,
+    pub column: usize,
+    pub pressed: bool,
+}
+
+pub struct Replay {
+    pub map_path: String,
+    pub mods: Mods,
+    // seed of the Random mod's column shuffle
+    pub seed: u32,
+    pub events: Vec<ReplayEvent>,
+}
+
+fn invalid_data(path: &Path, reason: &str) -> quicksilver::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Invalid replay {}: {}", path.display(), reason),
+    )
+    .into()
+}
+
+fn parse_event(line: &str) -> Option<ReplayEvent> {
+    let mut parts = line.split_whitespace();
+    let position = parts.next()?.parse().ok()?;
+    let column = parts.next()?.parse().ok()?;
+    let pressed = match parts.next()? {
+        "1" => true,
+        "0" => false,
+        _ => return None,
+    };
+    Some(ReplayEvent {
+        position,
+        column,
+        pressed,
+    })
+}
+
+impl Replay {
+    pub fn new(map_path: &str, mods: Mods, seed: u32) -> Replay {
+        Replay {
+            map_path: map_path.to_string(),
+            mods,
+            seed,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, events: &[KeyEvent], hotkeys: &[Key]) {
+        for event in events {
+            if let Some(column) = hotkeys.iter().position(|key| *key == event.key) {
+                self.events.push(ReplayEvent {
+                    position: event.position,
+                    column,
+                    pressed: event.pressed,
+                });
+            }
+        }
+    }
+
+    // replays/<map name> <unix time>.rpl
+    pub fn default_path(&self) -> PathBuf {
+        let map_name = Path::new(&self.map_path)
+            .file_stem()
+            .map_or("replay".into(), |stem| stem.to_string_lossy());
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Path::new(REPLAY_DIRECTORY).join(format!("{} {}.rpl", map_name, time))
+    }
+
+    pub fn load(path: &Path) -> Result<Replay> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        if lines
+            .next()
+            .transpose()?
+            .map_or(true, |line| line != HEADER)
+        {
+            return Err(invalid_data(path, "missing header"));
+        }
+        let map_path = lines
+            .next()
+            .transpose()?
+            .and_then(|line| line.strip_prefix("map: ").map(str::to_string))
+            .ok_or_else(|| invalid_data(path, "missing map"))?;
+        let mut mods = Mods::default();
+        let mut seed = 0;
+        let mut events = Vec::new();
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("mods: ") {
+                mods = Mods::parse(rest);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("seed: ") {
+                seed = rest.trim().parse().map_err(|_| invalid_data(path, &line))?;
+                continue;
+            }
+            events.push(parse_event(&line).ok_or_else(|| invalid_data(path, &line))?);
+        }
+        Ok(Replay {
+            map_path,
+            mods,
+            seed,
+            events,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", HEADER)?;
+        writeln!(file, "map: {}", self.map_path)?;
+        writeln!(file, "mods: {}", self.mods)?;
+        writeln!(file, "seed: {}", self.seed)?;
+        // f32 Display round-trips, so positions are restored bit for bit
+        for event in &self.events {
+            writeln!(
+                file,
+                "{} {} {}",
+                event.position,
+                event.column,
+                if event.pressed { 1 } else { 0 }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ReplayPlayer {
+    replay: Replay,
+    next: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer { replay, next: 0 }
+    }
+
+    pub fn restart(&mut self) {
+        self.next = 0;
+    }
+
+    // every event up to `position`, with the keys the lanes are currently bound to
+    pub fn events_until(&mut self, position: f32, hotkeys: &[Key]) -> Vec<KeyEvent> {
+        let mut events = Vec::new();
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.position > position {
+                break;
+            }
+            if let Some(key) = hotkeys.get(event.column) {
+                events.push(KeyEvent {
+                    key: *key,
+                    pressed: event.pressed,
+                    position: event.position,
+                });
+            }
+            self.next += 1;
+        }
+        events
+    }
+}
@@ -0,0 +1,209 @@
+use quicksilver::{
+    geom::{Line, Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Col, Img},
+        Color, Image,
+    },
+    input::Key,
+    lifecycle::{Asset, Window},
+    Result,
+};
+
+use crate::hit_score::HitResult;
+use crate::mods::{ModIcons, Mods};
+use crate::number::Number;
+use crate::scene::{Scene, Transition};
+use crate::score::Score;
+
+const JUDGEMENT_ROWS: [(HitResult, &str); 6] = [
+    (HitResult::Hit300g, "static/skin/hit300g.png"),
+    (HitResult::Hit300, "static/skin/hit300.png"),
+    (HitResult::Hit200, "static/skin/hit300k.png"),
+    (HitResult::Hit100, "static/skin/hit100.png"),
+    (HitResult::Hit50, "static/skin/hit50.png"),
+    (HitResult::Miss, "static/skin/hit0.png"),
+];
+
+fn draw_image(asset: &mut Asset<Image>, window: &mut Window, center: Vector, scale: f32) {
+    asset
+        .execute(|image| {
+            window.draw_ex(
+                &image.area().with_center(center),
+                Img(&image),
+                Transform::scale((scale, scale)),
+                1,
+            );
+            Ok(())
+        })
+        .expect("Failed to draw results screen");
+}
+
+pub struct Results {
+    score: Score,
+    mods: Mods,
+    mod_icons: ModIcons,
+    number: Number,
+    asset_grade: Asset<Image>,
+    asset_panel: Asset<Image>,
+    asset_graph: Asset<Image>,
+    asset_max_combo: Asset<Image>,
+    asset_accuracy: Asset<Image>,
+    asset_judgements: Vec<Asset<Image>>,
+}
+
+impl Results {
+    pub fn new(score: &Score, mods: Mods) -> Result<Results> {
+        Ok(Results {
+            score: score.clone(),
+            mods,
+            mod_icons: ModIcons::new(),
+            number: Number::new("score")?
+                .with_symbol('.', "static/skin/score-dot.png")
+                .with_symbol('%', "static/skin/score-percent.png")
+                .with_symbol('x', "static/skin/score-x.png"),
+            asset_grade: Asset::new(Image::load(score.grade(mods).image_path())),
+            asset_panel: Asset::new(Image::load("static/skin/ranking-panel.png")),
+            asset_graph: Asset::new(Image::load("static/skin/ranking-graph.png")),
+            asset_max_combo: Asset::new(Image::load("static/skin/ranking-maxcombo.png")),
+            asset_accuracy: Asset::new(Image::load("static/skin/ranking-accuracy.png")),
+            asset_judgements: JUDGEMENT_ROWS
+                .iter()
+                .map(|(_, path)| Asset::new(Image::load(*path)))
+                .collect(),
+        })
+    }
+
+    fn draw_graph(&mut self, window: &mut Window, area: Rectangle) {
+        let hit_errors = self.score.hit_errors();
+        let last_position = hit_errors
+            .iter()
+            .map(|(position, _)| *position)
+            .fold(1.0, f32::max);
+        let max_offset = hit_errors
+            .iter()
+            .map(|(_, offset)| offset.abs())
+            .fold(1.0, f32::max);
+        let center_y = area.pos.y + area.size.y / 2.0;
+
+        window.draw_ex(
+            &Line::new((area.pos.x, center_y), (area.pos.x + area.size.x, center_y)),
+            Col(Color::WHITE.with_alpha(0.5)),
+            Transform::IDENTITY,
+            2,
+        );
+        for (position, offset) in hit_errors {
+            let color = if *offset < 0.0 {
+                Color::from_rgba(80, 160, 255, 0.8)
+            } else {
+                Color::from_rgba(255, 120, 80, 0.8)
+            };
+            window.draw_ex(
+                &Rectangle::new((0, 0), (3, 3)).with_center((
+                    area.pos.x + area.size.x * position / last_position,
+                    center_y + area.size.y / 2.0 * offset / max_offset,
+                )),
+                Col(color),
+                Transform::IDENTITY,
+                3,
+            );
+        }
+    }
+}
+
+impl Scene for Results {
+    fn update(&mut self, window: &mut Window) -> Result<Transition> {
+        if window.keyboard()[Key::Escape].is_down() {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let Vector { x: w, y: h } = window.screen_size();
+        window.clear(Color::BLACK)?;
+
+        let mut panel_size = Vector::new(w / 2.0, h * 0.6);
+        self.asset_panel.execute(|image| {
+            panel_size = image.area().size;
+            window.draw_ex(
+                &Rectangle::new((0, h * 0.1), image.area().size),
+                Img(&image),
+                Transform::IDENTITY,
+                0,
+            );
+            Ok(())
+        })?;
+
+        self.number.draw(
+            window,
+            &Vector::new(panel_size.x * 0.3, h * 0.1 + 20.0),
+            &Vector::new(5, 5),
+            self.score.score(),
+        );
+
+        let row_height = panel_size.y * 0.16;
+        for (i, (result, _)) in JUDGEMENT_ROWS.iter().enumerate() {
+            let center = Vector::new(
+                panel_size.x * (0.12 + 0.45 * (i % 2) as f32),
+                h * 0.1 + panel_size.y * 0.25 + row_height * (i / 2) as f32,
+            );
+            draw_image(&mut self.asset_judgements[i], window, center, 0.5);
+            self.number.draw_str(
+                window,
+                &Vector::new(center.x + 50.0, center.y - 20.0),
+                &format!("{}x", self.score.count(*result)),
+            );
+        }
+
+        let stats_y = h * 0.1 + panel_size.y * 0.75;
+        draw_image(
+            &mut self.asset_max_combo,
+            window,
+            Vector::new(panel_size.x * 0.15, stats_y),
+            1.0,
+        );
+        self.number.draw_str(
+            window,
+            &Vector::new(panel_size.x * 0.05, stats_y + 20.0),
+            &format!("{}x", self.score.max_combo()),
+        );
+        draw_image(
+            &mut self.asset_accuracy,
+            window,
+            Vector::new(panel_size.x * 0.6, stats_y),
+            1.0,
+        );
+        self.number.draw_str(
+            window,
+            &Vector::new(panel_size.x * 0.5, stats_y + 20.0),
+            &format!("{:.2}%", self.score.accuracy() * 100.0),
+        );
+
+        draw_image(
+            &mut self.asset_grade,
+            window,
+            Vector::new(w * 0.75, h * 0.4),
+            1.0,
+        );
+        self.mod_icons
+            .draw(window, self.mods, Vector::new(w - 10.0, h * 0.1))?;
+
+        let mut graph_area = Rectangle::new((w * 0.55, h * 0.75), (w * 0.4, h * 0.2));
+        self.asset_graph.execute(|image| {
+            graph_area = image.area().with_center((w * 0.75, h * 0.85));
+            window.draw_ex(&graph_area, Img(&image), Transform::IDENTITY, 1);
+            Ok(())
+        })?;
+        self.draw_graph(window, graph_area);
+        self.number.draw_str(
+            window,
+            &Vector::new(
+                graph_area.pos.x,
+                graph_area.pos.y + graph_area.size.y + 10.0,
+            ),
+            &format!("{:.2}", self.score.unstable_rate()),
+        );
+
+        Ok(())
+    }
+}
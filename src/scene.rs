@@ -0,0 +1,21 @@
+use quicksilver::{
+    lifecycle::{Event, Window},
+    Result,
+};
+
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),
+    Replace(Box<dyn Scene>),
+    Pop,
+}
+
+pub trait Scene {
+    fn update(&mut self, window: &mut Window) -> Result<Transition>;
+
+    fn event(&mut self, _event: &Event, _window: &mut Window) -> Result<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()>;
+}
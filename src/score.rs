@@ -0,0 +1,318 @@
+use crate::hit_score::HitResult;
+use crate::judgement::Judgement;
+use crate::mods::{Mod, Mods};
+
+const MAX_SCORE: f64 = 1_000_000.0;
+const MAX_BONUS: f64 = 100.0;
+// ScoreV2 weights MAX slightly above 300 so that accuracy can tell them apart
+const V2_MAX_VALUE: f64 = 305.0;
+const V2_ACCURACY_PORTION: f64 = 0.99;
+const V2_COMBO_PORTION: f64 = 0.01;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum ScoreMode {
+    V1,
+    V2,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Grade {
+    XH,
+    X,
+    SH,
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    pub fn image_path(self) -> &'static str {
+        match self {
+            Grade::XH => "static/skin/ranking-XH.png",
+            Grade::X => "static/skin/ranking-X.png",
+            Grade::SH => "static/skin/ranking-SH.PNG",
+            Grade::S => "static/skin/ranking-S.png",
+            Grade::A => "static/skin/ranking-A.png",
+            Grade::B => "static/skin/ranking-B.png",
+            Grade::C => "static/skin/ranking-C.png",
+            Grade::D => "static/skin/ranking-D.png",
+        }
+    }
+}
+
+fn result_index(result: HitResult) -> usize {
+    match result {
+        HitResult::Hit300g => 0,
+        HitResult::Hit300 => 1,
+        HitResult::Hit200 => 2,
+        HitResult::Hit100 => 3,
+        HitResult::Hit50 => 4,
+        HitResult::Miss => 5,
+        HitResult::NoHit => unreachable!(),
+    }
+}
+
+fn hit_value(result: HitResult) -> f64 {
+    match result {
+        HitResult::Hit300g => 320.0,
+        HitResult::Hit300 => 300.0,
+        HitResult::Hit200 => 200.0,
+        HitResult::Hit100 => 100.0,
+        HitResult::Hit50 => 50.0,
+        _ => 0.0,
+    }
+}
+
+fn hit_bonus_value(result: HitResult) -> f64 {
+    match result {
+        HitResult::Hit300g | HitResult::Hit300 => 32.0,
+        HitResult::Hit200 => 16.0,
+        HitResult::Hit100 => 8.0,
+        HitResult::Hit50 => 4.0,
+        _ => 0.0,
+    }
+}
+
+fn hit_bonus(result: HitResult) -> f64 {
+    match result {
+        HitResult::Hit300g => 2.0,
+        HitResult::Hit300 => 1.0,
+        HitResult::Hit200 => -8.0,
+        HitResult::Hit100 => -24.0,
+        HitResult::Hit50 => -44.0,
+        _ => -MAX_BONUS,
+    }
+}
+
+#[derive(Clone)]
+pub struct Score {
+    mode: ScoreMode,
+    total_judgements: usize,
+    counts: [u32; 6],
+    combo: u32,
+    max_combo: u32,
+    bonus: f64,
+    score_v1: f64,
+    multiplier: f64,
+    hit_errors: Vec<(f32, f32)>,
+}
+
+impl Score {
+    pub fn new(total_judgements: usize, mode: ScoreMode) -> Score {
+        Score {
+            mode,
+            total_judgements: total_judgements.max(1),
+            counts: [0; 6],
+            combo: 0,
+            max_combo: 0,
+            bonus: MAX_BONUS,
+            score_v1: 0.0,
+            multiplier: 1.0,
+            hit_errors: Vec::new(),
+        }
+    }
+
+    // mods scale the final score
+    pub fn with_multiplier(mut self, multiplier: f32) -> Score {
+        self.multiplier = multiplier as f64;
+        self
+    }
+
+    pub fn reset(&mut self) {
+        *self =
+            Score::new(self.total_judgements, self.mode).with_multiplier(self.multiplier as f32);
+    }
+
+    pub fn apply(&mut self, judgement: Judgement) {
+        let result = judgement.result;
+        if result == HitResult::NoHit {
+            return;
+        }
+        if let Some(offset) = judgement.offset {
+            self.hit_errors.push((judgement.position, offset));
+        }
+        self.counts[result_index(result)] += 1;
+        if result == HitResult::Miss {
+            self.combo = 0;
+        } else {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo);
+        }
+
+        let note_score = MAX_SCORE * 0.5 / self.total_judgements as f64;
+        self.bonus = (self.bonus + hit_bonus(result)).clamp(0.0, MAX_BONUS);
+        self.score_v1 += note_score * hit_value(result) / 320.0
+            + note_score * hit_bonus_value(result) * self.bonus.sqrt() / 320.0;
+    }
+
+    pub fn count(&self, result: HitResult) -> u32 {
+        self.counts[result_index(result)]
+    }
+
+    pub fn judgements(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    pub fn max_combo(&self) -> u32 {
+        self.max_combo
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        let judgements = self.judgements();
+        if judgements == 0 {
+            return 1.0;
+        }
+        let weighted = 300 * (self.count(HitResult::Hit300g) + self.count(HitResult::Hit300))
+            + 200 * self.count(HitResult::Hit200)
+            + 100 * self.count(HitResult::Hit100)
+            + 50 * self.count(HitResult::Hit50);
+        weighted as f32 / (300 * judgements) as f32
+    }
+
+    // the silver SS and S are for passes that hid the notes
+    pub fn grade(&self, mods: Mods) -> Grade {
+        let accuracy = self.accuracy();
+        let silver = [Mod::Hidden, Mod::FadeIn, Mod::Flashlight]
+            .iter()
+            .any(|m| mods.contains(*m));
+        if accuracy >= 1.0 {
+            if silver {
+                Grade::XH
+            } else {
+                Grade::X
+            }
+        } else if accuracy > 0.95 {
+            if silver {
+                Grade::SH
+            } else {
+                Grade::S
+            }
+        } else if accuracy > 0.9 {
+            Grade::A
+        } else if accuracy > 0.8 {
+            Grade::B
+        } else if accuracy > 0.7 {
+            Grade::C
+        } else {
+            Grade::D
+        }
+    }
+
+    // (song position, offset) of every judgement that was hit
+    pub fn hit_errors(&self) -> &[(f32, f32)] {
+        &self.hit_errors
+    }
+
+    pub fn unstable_rate(&self) -> f32 {
+        if self.hit_errors.is_empty() {
+            return 0.0;
+        }
+        let count = self.hit_errors.len() as f32;
+        let mean = self
+            .hit_errors
+            .iter()
+            .map(|(_, offset)| offset)
+            .sum::<f32>()
+            / count;
+        let variance = self
+            .hit_errors
+            .iter()
+            .map(|(_, offset)| (offset - mean).powi(2))
+            .sum::<f32>()
+            / count;
+        variance.sqrt() * 10.0
+    }
+
+    fn score_v2(&self) -> f64 {
+        let weighted = V2_MAX_VALUE * self.count(HitResult::Hit300g) as f64
+            + 300.0 * self.count(HitResult::Hit300) as f64
+            + 200.0 * self.count(HitResult::Hit200) as f64
+            + 100.0 * self.count(HitResult::Hit100) as f64
+            + 50.0 * self.count(HitResult::Hit50) as f64;
+        let total = self.total_judgements as f64;
+        MAX_SCORE
+            * (V2_ACCURACY_PORTION * weighted / (V2_MAX_VALUE * total)
+                + V2_COMBO_PORTION * self.max_combo as f64 / total)
+    }
+
+    pub fn score(&self) -> u32 {
+        let score = match self.mode {
+            ScoreMode::V1 => self.score_v1,
+            ScoreMode::V2 => self.score_v2(),
+        };
+        (score * self.multiplier).min(MAX_SCORE).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(mode: ScoreMode, results: &[HitResult]) -> Score {
+        let mut score = Score::new(results.len(), mode);
+        for (i, result) in results.iter().enumerate() {
+            score.apply(Judgement::new(*result, i as f32 * 100.0, Some(0.0)));
+        }
+        score
+    }
+
+    #[test]
+    fn all_max_v1_is_a_million() {
+        let score = play(ScoreMode::V1, &[HitResult::Hit300g; 500]);
+        assert_eq!(score.score(), 1_000_000);
+        assert_eq!(score.max_combo(), 500);
+    }
+
+    #[test]
+    fn all_max_v2_is_a_million() {
+        let score = play(ScoreMode::V2, &[HitResult::Hit300g; 500]);
+        assert_eq!(score.score(), 1_000_000);
+    }
+
+    #[test]
+    fn multiplier_scales_the_total() {
+        let mut score = Score::new(10, ScoreMode::V1).with_multiplier(0.5);
+        for _ in 0..10 {
+            score.apply(Judgement::new(HitResult::Hit300g, 0.0, Some(0.0)));
+        }
+        assert_eq!(score.score(), 500_000);
+    }
+
+    #[test]
+    fn miss_costs_score_and_combo() {
+        let mut results = [HitResult::Hit300g; 10];
+        results[4] = HitResult::Miss;
+        for mode in &[ScoreMode::V1, ScoreMode::V2] {
+            let score = play(*mode, &results);
+            assert!(score.score() < 1_000_000);
+            assert_eq!(score.combo(), 5);
+            assert_eq!(score.max_combo(), 5);
+        }
+    }
+
+    #[test]
+    fn grades() {
+        let mut hidden = Mods::default();
+        hidden.toggle(Mod::Hidden);
+        let perfect = play(ScoreMode::V1, &[HitResult::Hit300; 20]);
+        assert_eq!(perfect.grade(Mods::default()), Grade::X);
+        assert_eq!(perfect.grade(hidden), Grade::XH);
+
+        let mut results = [HitResult::Hit300g; 20];
+        results[0] = HitResult::Hit200;
+        let s = play(ScoreMode::V1, &results);
+        assert_eq!(s.grade(Mods::default()), Grade::S);
+        assert_eq!(s.grade(hidden), Grade::SH);
+
+        assert_eq!(
+            play(ScoreMode::V1, &[HitResult::Miss; 20]).grade(hidden),
+            Grade::D
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use crate::timeline::Timeline;
+
+// osu!mania: at scroll speed S a note is visible for 11485 / S ms
+const OSU_SCROLL_TIME: f32 = 11485.0;
+// StepMania: at 1x one beat is 64 pixels on a 480 pixel tall screen
+const PIXELS_PER_BEAT: f32 = 64.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScrollMode {
+    // osu!mania scroll speed, follows SV and BPM changes relative to the main BPM
+    Constant,
+    // multiplier of the beat spacing, follows BPM and SV
+    Bpm,
+    // scroll as if the whole map was at this BPM
+    CMod,
+    // scale so the fastest BPM scrolls at this BPM
+    MMod,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ScrollSpeed {
+    pub mode: ScrollMode,
+    pub value: f32,
+}
+
+impl Default for ScrollSpeed {
+    fn default() -> ScrollSpeed {
+        ScrollSpeed {
+            mode: ScrollMode::Constant,
+            value: 20.0,
+        }
+    }
+}
+
+impl fmt::Display for ScrollSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            ScrollMode::Constant => write!(f, "{}", self.value),
+            ScrollMode::Bpm => write!(f, "{}x", self.value),
+            ScrollMode::CMod => write!(f, "C{}", self.value),
+            ScrollMode::MMod => write!(f, "M{}", self.value),
+        }
+    }
+}
+
+impl ScrollSpeed {
+    // "24" for constant speed, "1.5x" for BPM scaled, "C500" or "M600" for the StepMania mods
+    pub fn parse(text: &str) -> Option<ScrollSpeed> {
+        let text = text.trim();
+        let (mode, value) = if text.ends_with('x') || text.ends_with('X') {
+            (ScrollMode::Bpm, &text[..text.len() - 1])
+        } else if text.starts_with('C') || text.starts_with('c') {
+            (ScrollMode::CMod, &text[1..])
+        } else if text.starts_with('M') || text.starts_with('m') {
+            (ScrollMode::MMod, &text[1..])
+        } else {
+            (ScrollMode::Constant, text)
+        };
+        let value = value.parse::<f32>().ok()?;
+        let (min, max, _) = ScrollSpeed::range(mode);
+        Some(ScrollSpeed {
+            mode,
+            value: value.clamp(min, max),
+        })
+    }
+
+    // (min, max, step) of the value for each mode
+    fn range(mode: ScrollMode) -> (f32, f32, f32) {
+        match mode {
+            ScrollMode::Constant => (1.0, 40.0, 1.0),
+            ScrollMode::Bpm => (0.25, 8.0, 0.25),
+            ScrollMode::CMod | ScrollMode::MMod => (25.0, 2000.0, 25.0),
+        }
+    }
+
+    pub fn adjust(&mut self, steps: i32) {
+        let (min, max, step) = ScrollSpeed::range(self.mode);
+        self.value = (self.value + step * steps as f32).clamp(min, max);
+    }
+
+    pub fn distance(&self, timeline: &Timeline, position: f32) -> f32 {
+        match self.mode {
+            ScrollMode::CMod => position,
+            ScrollMode::Constant | ScrollMode::Bpm | ScrollMode::MMod => {
+                timeline.distance(position)
+            }
+        }
+    }
+
+    // Pixels per unit of `distance`, `scale` is screen pixels per skin pixel and
+    // `hit_position` the height of the hit line in skin pixels
+    pub fn pixels_per_unit(&self, timeline: &Timeline, scale: f32, hit_position: f32) -> f32 {
+        let beat = PIXELS_PER_BEAT * scale;
+        match self.mode {
+            ScrollMode::Constant => hit_position * scale * self.value / OSU_SCROLL_TIME,
+            ScrollMode::Bpm => beat * self.value / timeline.base_beat_length(),
+            ScrollMode::CMod => beat * self.value / 60000.0,
+            ScrollMode::MMod => {
+                beat * self.value / 60000.0 * timeline.min_beat_length()
+                    / timeline.base_beat_length()
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use quicksilver::Result;
+
+// osu! skins describe the playfield in a 640x480 space that is scaled to the screen height
+pub const SKIN_WIDTH: f32 = 640.0;
+pub const SKIN_HEIGHT: f32 = 480.0;
+
+const DEFAULT_HIT_POSITION: f32 = 402.0;
+const DEFAULT_COLUMN_WIDTH: f32 = 30.0;
+const DEFAULT_COLUMN_LINE_WIDTH: f32 = 2.0;
+
+#[derive(Clone, Debug)]
+pub struct ManiaLayout {
+    pub keys: u32,
+    pub column_start: f32,
+    pub hit_position: f32,
+    pub column_width: Vec<f32>,
+    // one more entry than there are columns, the outer edges included
+    pub column_line_width: Vec<f32>,
+}
+
+impl ManiaLayout {
+    pub fn default_for(keys: u32) -> ManiaLayout {
+        let column_width = vec![DEFAULT_COLUMN_WIDTH; keys as usize];
+        ManiaLayout {
+            keys,
+            column_start: (SKIN_WIDTH - column_width.iter().sum::<f32>()) / 2.0,
+            hit_position: DEFAULT_HIT_POSITION,
+            column_width,
+            column_line_width: vec![DEFAULT_COLUMN_LINE_WIDTH; keys as usize + 1],
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let list = || -> Vec<f32> {
+            value
+                .split(',')
+                .filter_map(|width| width.trim().parse().ok())
+                .collect()
+        };
+        match key {
+            "ColumnStart" => self.column_start = value.parse().unwrap_or(self.column_start),
+            "HitPosition" => self.hit_position = value.parse().unwrap_or(self.hit_position),
+            "ColumnWidth" => {
+                for (width, parsed) in self.column_width.iter_mut().zip(list()) {
+                    *width = parsed;
+                }
+            }
+            "ColumnLineWidth" => {
+                for (width, parsed) in self.column_line_width.iter_mut().zip(list()) {
+                    *width = parsed;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // left edge of every column plus the right edge of the last one, in skin space
+    pub fn column_edges(&self) -> Vec<f32> {
+        let mut edges = vec![self.column_start];
+        for width in &self.column_width {
+            edges.push(edges[edges.len() - 1] + width);
+        }
+        edges
+    }
+}
+
+pub struct Skin {
+    pub name: String,
+    pub mania: Vec<ManiaLayout>,
+}
+
+impl Skin {
+    pub fn load(path: &str) -> Result<Skin> {
+        Ok(Skin::parse(&std::fs::read_to_string(path)?))
+    }
+
+    pub fn parse(text: &str) -> Skin {
+        let mut skin = Skin {
+            name: String::new(),
+            mania: Vec::new(),
+        };
+        let mut section = String::new();
+        // Keys has to come first in a [Mania] section, everything else is relative to it
+        let mut layout: Option<ManiaLayout> = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                skin.mania.extend(layout.take());
+                section = line[1..line.len() - 1].to_string();
+                continue;
+            }
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            match section.as_str() {
+                "General" if key == "Name" => skin.name = value.to_string(),
+                "Mania" if key == "Keys" => {
+                    skin.mania.extend(layout.take());
+                    layout = value.parse().ok().map(ManiaLayout::default_for);
+                }
+                "Mania" => {
+                    if let Some(layout) = layout.as_mut() {
+                        layout.set(key, value);
+                    }
+                }
+                _ => (),
+            }
+        }
+        skin.mania.extend(layout);
+        skin
+    }
+
+    pub fn mania(&self, keys: u32) -> ManiaLayout {
+        self.mania
+            .iter()
+            .find(|layout| layout.keys == keys)
+            .cloned()
+            .unwrap_or_else(|| ManiaLayout::default_for(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shipped() -> Skin {
+        Skin::parse(include_str!("../static/skin/Skin.ini"))
+    }
+
+    #[test]
+    fn general() {
+        assert_eq!(shipped().name, "Gopher");
+    }
+
+    #[test]
+    fn mania_4k() {
+        let layout = shipped().mania(4);
+        assert_eq!(layout.keys, 4);
+        assert_eq!(layout.column_start, 337.0);
+        assert_eq!(layout.hit_position, 430.0);
+        assert_eq!(layout.column_width, vec![45.0; 4]);
+        assert_eq!(layout.column_line_width, vec![0.0; 5]);
+        assert_eq!(
+            layout.column_edges(),
+            vec![337.0, 382.0, 427.0, 472.0, 517.0]
+        );
+    }
+
+    #[test]
+    fn mania_7k() {
+        let layout = shipped().mania(7);
+        assert_eq!(layout.keys, 7);
+        assert_eq!(layout.column_start, 302.0);
+        assert_eq!(layout.hit_position, 430.0);
+        assert_eq!(
+            layout.column_width,
+            vec![36.0, 34.0, 36.0, 38.0, 36.0, 34.0, 38.0]
+        );
+        assert_eq!(layout.column_line_width, vec![0.0; 8]);
+        assert_eq!(layout.column_edges().last(), Some(&554.0));
+    }
+
+    #[test]
+    fn missing_key_count_gets_the_default() {
+        let layout = shipped().mania(5);
+        assert_eq!(layout.hit_position, DEFAULT_HIT_POSITION);
+        assert_eq!(layout.column_width, vec![DEFAULT_COLUMN_WIDTH; 5]);
+        assert_eq!(layout.column_start, (SKIN_WIDTH - 150.0) / 2.0);
+    }
+}
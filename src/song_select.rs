@@ -0,0 +1,312 @@
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use quicksilver::{
+    geom::{Rectangle, Shape, Transform, Vector},
+    graphics::{
+        Background::{Blended, Img},
+        Color, Font, FontStyle, Image,
+    },
+    input::{ButtonState, Key},
+    lifecycle::{Asset, Event, Window},
+    Result,
+};
+
+use crate::binding_screen::BindingScreen;
+use crate::bindings;
+use crate::camera::Camera;
+use crate::library::{self, BeatmapInfo, BeatmapSet};
+use crate::mod_select::ModSelect;
+use crate::mods::{Mod, ModIcons, Mods};
+use crate::scene::{Scene, Transition};
+
+const VISIBLE_ROWS: usize = 4;
+const ROW_HEIGHT: f32 = 110.0;
+const BACK_FRAME_COUNT: usize = 59;
+const BACK_FRAME_DURATION: f32 = 1000.0 / 30.0;
+
+pub struct SongSelect {
+    sets: Vec<BeatmapSet>,
+    // (set, difficulty) for every row of the list
+    entries: Vec<(usize, usize)>,
+    selected: usize,
+    pending: Option<Transition>,
+    // why the last beatmap didn't start, until the next key press
+    error: Option<String>,
+    // shared with the mod select screen
+    mods: Rc<Cell<Mods>>,
+    mod_icons: ModIcons,
+    opened: Instant,
+    font: Asset<Font>,
+    asset_background: Asset<Image>,
+    asset_button: Asset<Image>,
+    asset_back: Vec<Asset<Image>>,
+    asset_bar_buttons: Vec<Asset<Image>>,
+}
+
+impl SongSelect {
+    pub fn new(songs_directory: &str) -> Result<SongSelect> {
+        let sets = library::scan(Path::new(songs_directory))?;
+        let entries = sets
+            .iter()
+            .enumerate()
+            .flat_map(|(set_index, set)| {
+                (0..set.difficulties.len()).map(move |difficulty| (set_index, difficulty))
+            })
+            .collect();
+
+        Ok(SongSelect {
+            sets,
+            entries,
+            selected: 0,
+            pending: None,
+            error: None,
+            mods: Rc::new(Cell::new(Mods::from_args())),
+            mod_icons: ModIcons::new(),
+            opened: Instant::now(),
+            font: Asset::new(Font::load("static/fonts/DejaVuSans.ttf")),
+            asset_background: Asset::new(Image::load("static/skin/menu-background.jpg")),
+            asset_button: Asset::new(Image::load("static/skin/menu-button-background.png")),
+            asset_back: (0..BACK_FRAME_COUNT)
+                .map(|frame| {
+                    Asset::new(Image::load(format!(
+                        "static/skin/menu-back-{}@2x.png",
+                        frame
+                    )))
+                })
+                .collect(),
+            asset_bar_buttons: ["mode", "mods", "random", "options"]
+                .iter()
+                .map(|name| Asset::new(Image::load(format!("static/skin/selection-{}.png", name))))
+                .collect(),
+        })
+    }
+
+    fn selected_beatmap(&self) -> Option<&BeatmapInfo> {
+        self.entries
+            .get(self.selected)
+            .map(|(set, difficulty)| &self.sets[*set].difficulties[*difficulty])
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        self.selected = (self.selected as i32 + delta)
+            .max(0)
+            .min(self.entries.len() as i32 - 1) as usize;
+    }
+
+    fn move_set(&mut self, delta: i32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let (set_index, _) = self.entries[self.selected];
+        let set_index = (set_index as i32 + delta)
+            .max(0)
+            .min(self.sets.len() as i32 - 1) as usize;
+        self.select_set(set_index);
+    }
+
+    fn select_set(&mut self, set_index: usize) {
+        if let Some(row) = self.entries.iter().position(|(set, _)| *set == set_index) {
+            self.selected = row;
+        }
+    }
+
+    fn select_random(&mut self) {
+        if self.sets.is_empty() {
+            return;
+        }
+        let seed = self.opened.elapsed().subsec_nanos() as usize;
+        self.select_set(seed % self.sets.len());
+    }
+
+    fn visible_rows(&self) -> std::ops::Range<usize> {
+        let first = self.selected.saturating_sub(VISIBLE_ROWS);
+        first..(self.selected + VISIBLE_ROWS + 1).min(self.entries.len())
+    }
+
+    fn row_position(&self, row: usize, screen_size: Vector) -> Vector {
+        let offset = row as f32 - self.selected as f32;
+        let indent = if row == self.selected { 0.0 } else { 60.0 };
+        Vector::new(
+            screen_size.x * 0.45 + indent,
+            screen_size.y / 2.0 + offset * ROW_HEIGHT - ROW_HEIGHT / 2.0,
+        )
+    }
+}
+
+impl Scene for SongSelect {
+    fn update(&mut self, _window: &mut Window) -> Result<Transition> {
+        Ok(self.pending.take().unwrap_or(Transition::None))
+    }
+
+    fn event(&mut self, event: &Event, window: &mut Window) -> Result<()> {
+        if let Event::Key(key, ButtonState::Pressed) = event {
+            self.error = None;
+            match key {
+                Key::Escape => std::process::exit(0),
+                Key::Up => self.move_selection(-1),
+                Key::Down => self.move_selection(1),
+                Key::Left => self.move_set(-1),
+                Key::Right => self.move_set(1),
+                Key::F2 => self.select_random(),
+                Key::F1 => {
+                    let mod_select = ModSelect::new(self.mods.clone())?;
+                    self.pending = Some(Transition::Push(Box::new(mod_select)));
+                }
+                Key::O => {
+                    let keys = self
+                        .selected_beatmap()
+                        .map(|info| info.key_count)
+                        .filter(|keys| bindings::is_supported(*keys))
+                        .unwrap_or(4);
+                    self.pending = Some(Transition::Push(Box::new(BindingScreen::new(keys)?)));
+                }
+                Key::Return => {
+                    if let Some(info) = self.selected_beatmap() {
+                        let path = info.path.to_string_lossy();
+                        // Ctrl+Enter watches autoplay like in osu!
+                        let keyboard = window.keyboard();
+                        let mut mods = self.mods.get();
+                        if (keyboard[Key::LControl].is_down() || keyboard[Key::RControl].is_down())
+                            && !mods.is_autoplay()
+                        {
+                            mods.toggle(Mod::Autoplay);
+                        }
+                        // a broken map or missing song only fails this beatmap
+                        match Camera::new(&path, mods) {
+                            Ok(camera) => {
+                                self.pending = Some(Transition::Push(Box::new(camera)));
+                            }
+                            Err(err) => {
+                                eprintln!("Failed to start {}: {:?}", path, err);
+                                self.error = Some(format!("Failed to start {}: {}", path, err));
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, window: &mut Window) -> Result<()> {
+        let screen_size = window.screen_size();
+        let Vector { x: w, y: h } = screen_size;
+
+        self.asset_background.execute(|image| {
+            window.draw_ex(
+                &Rectangle::new((0, 0), (w, h)),
+                Img(&image),
+                Transform::IDENTITY,
+                -1,
+            );
+            Ok(())
+        })?;
+
+        let rows: Vec<(usize, Vector)> = self
+            .visible_rows()
+            .map(|row| (row, self.row_position(row, screen_size)))
+            .collect();
+        let selected = self.selected;
+        self.asset_button.execute(|image| {
+            for (row, pos) in &rows {
+                let tint = if *row == selected {
+                    Color::WHITE
+                } else {
+                    Color::from_rgba(150, 150, 200, 0.8)
+                };
+                window.draw_ex(
+                    &Rectangle::new(*pos, image.area().size),
+                    Blended(&image, tint),
+                    Transform::IDENTITY,
+                    0,
+                );
+            }
+            Ok(())
+        })?;
+
+        let sets = &self.sets;
+        let entries = &self.entries;
+        self.font.execute(|font| {
+            for (row, pos) in &rows {
+                let (set, difficulty) = entries[*row];
+                let info = &sets[set].difficulties[difficulty];
+                let title = font.render(
+                    &format!("{} - {}", info.artist, info.title),
+                    &FontStyle::new(32.0, Color::WHITE),
+                )?;
+                let details = font.render(
+                    &format!(
+                        "[{}] {}K  mapped by {}",
+                        info.version, info.key_count, info.creator
+                    ),
+                    &FontStyle::new(24.0, Color::from_rgba(230, 230, 230, 1.0)),
+                )?;
+                window.draw_ex(
+                    &title.area().translate(*pos + Vector::new(40, 20)),
+                    Img(&title),
+                    Transform::IDENTITY,
+                    1,
+                );
+                window.draw_ex(
+                    &details.area().translate(*pos + Vector::new(40, 62)),
+                    Img(&details),
+                    Transform::IDENTITY,
+                    1,
+                );
+            }
+            Ok(())
+        })?;
+
+        let frame = (self.opened.elapsed().as_secs_f32() * 1000.0 / BACK_FRAME_DURATION) as usize
+            % self.asset_back.len();
+        let mut bar_x = 0.0;
+        self.asset_back[frame].execute(|image| {
+            let size = image.area().size / 2.0;
+            bar_x = size.x;
+            window.draw_ex(
+                &Rectangle::new((0.0, h - size.y), size),
+                Img(&image),
+                Transform::IDENTITY,
+                2,
+            );
+            Ok(())
+        })?;
+        for button in &mut self.asset_bar_buttons {
+            button.execute(|image| {
+                let size = image.area().size;
+                window.draw_ex(
+                    &Rectangle::new((bar_x, h - size.y), size),
+                    Img(&image),
+                    Transform::IDENTITY,
+                    2,
+                );
+                bar_x += size.x;
+                Ok(())
+            })?;
+        }
+        self.mod_icons
+            .draw(window, self.mods.get(), Vector::new(w - 10.0, h - 80.0))?;
+
+        if let Some(error) = &self.error {
+            self.font.execute(|font| {
+                let text = font.render(error, &FontStyle::new(24.0, Color::RED))?;
+                window.draw_ex(
+                    &text.area().translate((10.0, 10.0)),
+                    Img(&text),
+                    Transform::IDENTITY,
+                    3,
+                );
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use rodio::{Sample, Source};
+use std::f32::consts::PI;
+use std::time::Duration;
+
+// WSOLA: overlap-add windows of the input at `rate` times the output hop, each shifted
+// within the search range to line up with what the previous window would have continued as
+const WINDOW_MS: f32 = 40.0;
+const SEARCH_MS: f32 = 10.0;
+// only every few frames are compared while searching, it's plenty for finding the phase
+const SEARCH_STRIDE: usize = 4;
+
+pub struct TimeStretch<S> {
+    inner: S,
+    channels: usize,
+    sample_rate: u32,
+    rate: f64,
+    window: Vec<f32>,
+    search: usize,
+    // interleaved input, `input_start` is the frame index of its first frame
+    input: Vec<f32>,
+    input_start: usize,
+    input_finished: bool,
+    analysis_position: f64,
+    previous: Option<usize>,
+    // second half of the previous window, waiting to be overlapped
+    tail: Vec<f32>,
+    output: Vec<f32>,
+    output_index: usize,
+}
+
+impl<S> TimeStretch<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    // rate > 1 plays faster, the pitch stays the same
+    pub fn new(inner: S, rate: f32) -> TimeStretch<S> {
+        let channels = inner.channels() as usize;
+        let sample_rate = inner.sample_rate();
+        let window_frames = ((sample_rate as f32 * WINDOW_MS / 1000.0) as usize / 2 * 2).max(2);
+        let window = (0..window_frames)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / window_frames as f32).cos())
+            .collect();
+        TimeStretch {
+            inner,
+            channels,
+            sample_rate,
+            rate: rate as f64,
+            window,
+            search: (sample_rate as f32 * SEARCH_MS / 1000.0) as usize,
+            input: Vec::new(),
+            input_start: 0,
+            input_finished: false,
+            analysis_position: 0.0,
+            previous: None,
+            tail: vec![0.0; window_frames / 2 * channels],
+            output: Vec::new(),
+            output_index: 0,
+        }
+    }
+
+    fn hop(&self) -> usize {
+        self.window.len() / 2
+    }
+
+    fn input_end(&self) -> usize {
+        self.input_start + self.input.len() / self.channels
+    }
+
+    fn fill_input(&mut self, end_frame: usize) {
+        while !self.input_finished && self.input_end() < end_frame {
+            for _ in 0..self.channels {
+                match self.inner.next() {
+                    Some(sample) => self.input.push(sample.to_f32()),
+                    None => {
+                        self.input_finished = true;
+                        break;
+                    }
+                }
+            }
+        }
+        // drop a partial frame left by a source that ended mid-frame
+        let whole_frames = self.input.len() / self.channels * self.channels;
+        self.input.truncate(whole_frames);
+    }
+
+    fn frame(&self, frame: usize, channel: usize) -> f32 {
+        if frame < self.input_start {
+            return 0.0;
+        }
+        self.input
+            .get((frame - self.input_start) * self.channels + channel)
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    // picks the start around `nominal` that continues the previous window most smoothly
+    fn best_start(&self, nominal: usize) -> usize {
+        let target = match self.previous {
+            Some(previous) => previous + self.hop(),
+            None => return nominal,
+        };
+        let first = nominal.saturating_sub(self.search).max(self.input_start);
+        let last = nominal + self.search;
+        let mut best = (nominal, f32::MIN);
+        for start in first..=last {
+            let mut correlation = 0.0;
+            for i in (0..self.hop()).step_by(SEARCH_STRIDE) {
+                for channel in 0..self.channels {
+                    correlation += self.frame(start + i, channel) * self.frame(target + i, channel);
+                }
+            }
+            if correlation > best.1 {
+                best = (start, correlation);
+            }
+        }
+        best.0
+    }
+
+    fn process_window(&mut self) -> bool {
+        let nominal = self.analysis_position as usize;
+        let window_frames = self.window.len();
+        let hop = self.hop();
+        self.fill_input(nominal + self.search + window_frames + hop);
+        if nominal >= self.input_end() {
+            if self.tail.is_empty() {
+                return false;
+            }
+            // flush the last half window
+            self.output = std::mem::take(&mut self.tail);
+            self.output_index = 0;
+            return true;
+        }
+
+        let start = self.best_start(nominal);
+        let mut output = Vec::with_capacity(hop * self.channels);
+        let mut tail = Vec::with_capacity(hop * self.channels);
+        for i in 0..window_frames {
+            for channel in 0..self.channels {
+                let sample = self.frame(start + i, channel) * self.window[i];
+                if i < hop {
+                    output.push(self.tail[i * self.channels + channel] + sample);
+                } else {
+                    tail.push(sample);
+                }
+            }
+        }
+        self.output = output;
+        self.output_index = 0;
+        self.tail = tail;
+        self.previous = Some(start);
+        self.analysis_position += hop as f64 * self.rate;
+
+        // keep only what the next searches can still reach
+        let keep_from = (self.analysis_position as usize)
+            .min(start + hop)
+            .saturating_sub(self.search);
+        if keep_from > self.input_start {
+            let drop = ((keep_from - self.input_start) * self.channels).min(self.input.len());
+            self.input.drain(..drop);
+            self.input_start += drop / self.channels;
+        }
+        true
+    }
+}
+
+impl<S> Iterator for TimeStretch<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.output_index >= self.output.len() && !self.process_window() {
+            return None;
+        }
+        let sample = self.output[self.output_index];
+        self.output_index += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for TimeStretch<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner
+            .total_duration()
+            .map(|duration| duration.div_f64(self.rate))
+    }
+}
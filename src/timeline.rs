@@ -0,0 +1,191 @@
+use osu_format::TimingPoint;
+
+// osu! clamps slider velocity multipliers to this range
+const MIN_SV: f32 = 0.1;
+const MAX_SV: f32 = 10.0;
+
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    start: f32,
+    distance: f32,
+    velocity: f32,
+}
+
+// Maps song positions to scroll distance, a note at `time` is
+// `distance(time) - distance(position)` away from the hit line.
+// Distance is measured in milliseconds at the base BPM and SV 1.
+pub struct Timeline {
+    segments: Vec<Segment>,
+    base_beat_length: f32,
+    min_beat_length: f32,
+}
+
+fn is_uninherited(timing_point: &TimingPoint) -> bool {
+    // green lines store the SV as a negative percentage
+    timing_point.milliseconds_per_beat > 0.0
+}
+
+// The beat length that lasts the longest, notes scroll at 1x speed during it
+fn dominant_beat_length(timing_points: &[&TimingPoint], end_position: f32) -> Option<f32> {
+    let uninherited: Vec<&&TimingPoint> = timing_points
+        .iter()
+        .filter(|timing_point| is_uninherited(timing_point))
+        .collect();
+    let mut durations: Vec<(f32, f32)> = Vec::new();
+    for (i, timing_point) in uninherited.iter().enumerate() {
+        let end = uninherited
+            .get(i + 1)
+            .map_or(end_position, |next| next.offset as f32);
+        let duration = (end - timing_point.offset as f32).max(0.0);
+        match durations.iter_mut().find(|(beat_length, _)| {
+            (*beat_length - timing_point.milliseconds_per_beat).abs() < 0.001
+        }) {
+            Some((_, total)) => *total += duration,
+            None => durations.push((timing_point.milliseconds_per_beat, duration)),
+        }
+    }
+    durations
+        .into_iter()
+        .fold(
+            None,
+            |best: Option<(f32, f32)>, (beat_length, duration)| match best {
+                Some((_, best_duration)) if best_duration >= duration => best,
+                _ => Some((beat_length, duration)),
+            },
+        )
+        .map(|(beat_length, _)| beat_length)
+}
+
+impl Timeline {
+    pub fn new(timing_points: &[TimingPoint], end_position: f32, honour_sv: bool) -> Timeline {
+        let mut sorted: Vec<&TimingPoint> = timing_points.iter().collect();
+        // red lines reset the SV, so they go before green lines on the same offset
+        sorted.sort_by_key(|timing_point| (timing_point.offset, !is_uninherited(timing_point)));
+
+        let base_beat_length = dominant_beat_length(&sorted, end_position).unwrap_or(500.0);
+        let min_beat_length = sorted
+            .iter()
+            .filter(|timing_point| is_uninherited(timing_point))
+            .map(|timing_point| timing_point.milliseconds_per_beat)
+            .fold(base_beat_length, f32::min);
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut beat_length = base_beat_length;
+        for timing_point in sorted {
+            let start = timing_point.offset as f32;
+            let sv = if is_uninherited(timing_point) {
+                beat_length = timing_point.milliseconds_per_beat;
+                1.0
+            } else if honour_sv {
+                (-100.0 / timing_point.milliseconds_per_beat).clamp(MIN_SV, MAX_SV)
+            } else {
+                1.0
+            };
+            let velocity = base_beat_length / beat_length * sv;
+
+            let distance = segments.last().map_or(0.0, |last| {
+                last.distance + (start - last.start) * last.velocity
+            });
+            match segments.last_mut() {
+                Some(last) if last.start == start => last.velocity = velocity,
+                _ => segments.push(Segment {
+                    start,
+                    distance,
+                    velocity,
+                }),
+            }
+        }
+
+        Timeline {
+            segments,
+            base_beat_length,
+            min_beat_length,
+        }
+    }
+
+    pub fn base_beat_length(&self) -> f32 {
+        self.base_beat_length
+    }
+
+    // beat length of the fastest BPM section
+    pub fn min_beat_length(&self) -> f32 {
+        self.min_beat_length
+    }
+
+    pub fn distance(&self, position: f32) -> f32 {
+        let index = match self
+            .segments
+            .binary_search_by(|segment| segment.start.partial_cmp(&position).unwrap())
+        {
+            Ok(index) => index,
+            Err(0) => 0,
+            Err(index) => index - 1,
+        };
+        match self.segments.get(index) {
+            Some(segment) => segment.distance + (position - segment.start) * segment.velocity,
+            None => position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // milliseconds_per_beat > 0 is a red line, otherwise -100 / SV
+    fn timing_point(offset: u32, milliseconds_per_beat: f32) -> TimingPoint {
+        TimingPoint {
+            offset,
+            milliseconds_per_beat,
+            meter: 4,
+            sample_set: 0,
+            sample_index: 0,
+            volume: 100,
+            inherited: milliseconds_per_beat < 0.0,
+            kiai_mode: false,
+        }
+    }
+
+    #[test]
+    fn sv_change_speeds_up_scrolling() {
+        let timing_points = [timing_point(0, 500.0), timing_point(1000, -50.0)];
+        let timeline = Timeline::new(&timing_points, 5000.0, true);
+        assert_eq!(timeline.distance(500.0), 500.0);
+        assert_eq!(timeline.distance(1000.0), 1000.0);
+        assert_eq!(timeline.distance(1500.0), 2000.0);
+    }
+
+    #[test]
+    fn sv_can_be_ignored() {
+        let timing_points = [timing_point(0, 500.0), timing_point(1000, -50.0)];
+        let timeline = Timeline::new(&timing_points, 5000.0, false);
+        assert_eq!(timeline.distance(1500.0), 1500.0);
+    }
+
+    #[test]
+    fn red_line_resets_sv() {
+        let timing_points = [
+            timing_point(0, 500.0),
+            timing_point(1000, -50.0),
+            timing_point(2000, 500.0),
+        ];
+        let timeline = Timeline::new(&timing_points, 5000.0, true);
+        assert_eq!(timeline.distance(2000.0), 3000.0);
+        assert_eq!(timeline.distance(2500.0), 3500.0);
+    }
+
+    #[test]
+    fn bpm_is_relative_to_the_longest_section() {
+        let timing_points = [timing_point(0, 500.0), timing_point(10000, 250.0)];
+        let timeline = Timeline::new(&timing_points, 12000.0, true);
+        assert_eq!(timeline.base_beat_length(), 500.0);
+        assert_eq!(timeline.min_beat_length(), 250.0);
+        assert_eq!(timeline.distance(11000.0), 12000.0);
+    }
+
+    #[test]
+    fn sv_is_clamped() {
+        let timing_points = [timing_point(0, 500.0), timing_point(1000, -1.0)];
+        let timeline = Timeline::new(&timing_points, 5000.0, true);
+        assert_eq!(timeline.distance(1100.0), 1000.0 + 100.0 * MAX_SV);
+    }
+}